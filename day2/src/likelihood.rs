@@ -0,0 +1,218 @@
+use crate::{Bag, Count, Game};
+use utils::SplitMix64;
+
+/// How the probability of a round is computed.
+pub enum Estimation {
+    /// Multivariate hypergeometric probability, exact for any bag size.
+    Exact,
+    /// Repeated seeded draws, used when the bags get too large to reason about exactly.
+    MonteCarlo { samples: u32, seed: u64 },
+}
+
+pub struct BagLikelihood {
+    pub bag: Bag,
+    pub likelihood: f64,
+}
+
+/// `ln(n choose k)`, minus infinity when there are fewer than `k` items to choose from.
+fn ln_choose(n: i64, k: i64) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    (0..k).map(|i| ((n - i) as f64 / (k - i) as f64).ln()).sum()
+}
+
+fn drawn_for(round: &[Count], color: &str) -> i64 {
+    round
+        .iter()
+        .filter(|item| item.color == color)
+        .map(|item| item.count)
+        .sum()
+}
+
+/// Smallest bag the game can come from: per colour, the largest total drawn in one round.
+/// A colour listed twice in a round counts both draws, unlike `minimal_bag`.
+fn smallest_bag(game: &Game) -> Bag {
+    let mut bag = Bag::new();
+    for round in &game.rounds {
+        for item in round {
+            let drawn = drawn_for(round, &item.color);
+            let max = bag.entry(item.color.clone()).or_insert(0);
+            *max = (*max).max(drawn);
+        }
+    }
+    bag
+}
+
+/// Probability of drawing exactly this round from the bag, every cube being put back afterwards.
+fn exact_round_probability(round: &[Count], bag: &Bag) -> f64 {
+    let total: i64 = bag.values().sum();
+    let drawn: i64 = round.iter().map(|item| item.count).sum();
+
+    let mut ln_p = -ln_choose(total, drawn);
+    for (color, count) in bag {
+        ln_p += ln_choose(*count, drawn_for(round, color));
+    }
+    ln_p.exp()
+}
+
+fn simulated_round_probability(
+    round: &[Count],
+    bag: &Bag,
+    samples: u32,
+    rng: &mut SplitMix64,
+) -> f64 {
+    let colors: Vec<&String> = bag.keys().collect();
+    let expected: Vec<i64> = colors.iter().map(|color| drawn_for(round, color)).collect();
+    let drawn = expected.iter().sum::<i64>() as usize;
+
+    let mut cubes: Vec<usize> = bag
+        .values()
+        .enumerate()
+        .flat_map(|(idx, count)| std::iter::repeat_n(idx, *count as usize))
+        .collect();
+
+    let mut matches = 0;
+    for _ in 0..samples {
+        // Partial Fisher-Yates: the first `drawn` cubes are the sample
+        for i in 0..drawn {
+            let j = i + rng.below(cubes.len() - i);
+            cubes.swap(i, j);
+        }
+        let mut counts = vec![0; colors.len()];
+        for cube in &cubes[..drawn] {
+            counts[*cube] += 1;
+        }
+        if counts == expected {
+            matches += 1;
+        }
+    }
+    matches as f64 / samples as f64
+}
+
+/// Likelihood of every round of the game being drawn from this bag.
+pub fn game_likelihood(game: &Game, bag: &Bag, estimation: &Estimation) -> f64 {
+    // A bag smaller than what a single round takes out can't have produced the game
+    let feasible = smallest_bag(game)
+        .iter()
+        .all(|(color, count)| bag.get(color).is_some_and(|limit| limit >= count));
+    if !feasible {
+        return 0.0;
+    }
+
+    match estimation {
+        Estimation::Exact => game
+            .rounds
+            .iter()
+            .map(|round| exact_round_probability(round, bag))
+            .product(),
+        Estimation::MonteCarlo { samples, seed } => {
+            let mut rng = SplitMix64(*seed);
+            game.rounds
+                .iter()
+                .map(|round| simulated_round_probability(round, bag, *samples, &mut rng))
+                .product()
+        }
+    }
+}
+
+/// Every bag holding between the smallest possible bag and `extra` more cubes of each colour.
+pub fn candidate_bags(game: &Game, extra: i64) -> Vec<Bag> {
+    let mut candidates = vec![Bag::new()];
+    for (color, count) in smallest_bag(game) {
+        candidates = candidates
+            .into_iter()
+            .flat_map(|bag| {
+                let color = color.clone();
+                (count..=count + extra).map(move |value| {
+                    let mut bag = bag.clone();
+                    bag.insert(color.clone(), value);
+                    bag
+                })
+            })
+            .collect();
+    }
+    candidates
+}
+
+/// Rank the candidate bags by likelihood and keep the `top` best ones.
+pub fn most_likely_bags(
+    game: &Game,
+    candidates: Vec<Bag>,
+    estimation: &Estimation,
+    top: usize,
+) -> Vec<BagLikelihood> {
+    let mut ranked: Vec<BagLikelihood> = candidates
+        .into_iter()
+        .map(|bag| BagLikelihood {
+            likelihood: game_likelihood(game, &bag, estimation),
+            bag,
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.likelihood.total_cmp(&a.likelihood));
+    ranked.truncate(top);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{candidate_bags, game_likelihood, most_likely_bags, Estimation};
+    use crate::{parse_game, Bag};
+
+    fn bag(red: i64, blue: i64) -> Bag {
+        Bag::from([("red".to_owned(), red), ("blue".to_owned(), blue)])
+    }
+
+    #[test]
+    fn should_compute_exact_likelihood() {
+        let game = parse_game("Game 1: 1 red; 1 blue");
+        let likelihood = game_likelihood(&game, &bag(1, 1), &Estimation::Exact);
+        assert!((likelihood - 0.25).abs() < 1e-9);
+        let likelihood = game_likelihood(&game, &bag(2, 1), &Estimation::Exact);
+        assert!((likelihood - 2.0 / 9.0).abs() < 1e-9);
+        let likelihood = game_likelihood(&game, &bag(1, 0), &Estimation::Exact);
+        assert_eq!(likelihood, 0.0);
+    }
+
+    #[test]
+    fn should_estimate_likelihood_with_monte_carlo() {
+        let game = parse_game("Game 1: 2 red, 1 blue; 1 blue");
+        let exact = game_likelihood(&game, &bag(3, 2), &Estimation::Exact);
+        let estimation = Estimation::MonteCarlo {
+            samples: 20_000,
+            seed: 42,
+        };
+        let simulated = game_likelihood(&game, &bag(3, 2), &estimation);
+        assert!((exact - simulated).abs() < 0.02);
+        assert_eq!(simulated, game_likelihood(&game, &bag(3, 2), &estimation));
+    }
+
+    #[test]
+    fn should_rank_most_likely_bags() {
+        let game = parse_game("Game 1: 1 red; 1 blue; 1 red");
+        let candidates = candidate_bags(&game, 2);
+        assert_eq!(candidates.len(), 9);
+
+        let ranked = most_likely_bags(&game, candidates, &Estimation::Exact, 3);
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0].bag, bag(2, 1));
+        assert!(ranked[0].likelihood >= ranked[1].likelihood);
+    }
+
+    #[test]
+    fn should_sum_repeated_colors_in_a_round() {
+        let game = parse_game("Game 1: 2 red, 2 red; 1 blue");
+        let candidates = candidate_bags(&game, 1);
+        assert!(candidates.iter().all(|candidate| candidate["red"] >= 4));
+        assert_eq!(game_likelihood(&game, &bag(2, 1), &Estimation::Exact), 0.0);
+
+        let estimation = Estimation::MonteCarlo {
+            samples: 1_000,
+            seed: 42,
+        };
+        assert_eq!(game_likelihood(&game, &bag(3, 1), &estimation), 0.0);
+        let ranked = most_likely_bags(&game, candidates, &Estimation::Exact, 4);
+        assert!(ranked[0].likelihood > 0.0);
+        assert!(ranked.iter().all(|ranked| !ranked.likelihood.is_nan()));
+    }
+}
@@ -1,19 +1,65 @@
+mod likelihood;
+mod streaming;
+
+use likelihood::{candidate_bags, most_likely_bags, Estimation};
 use regex::Regex;
-use std::io::{BufRead, Error};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, Error, ErrorKind};
+use streaming::{bench, solve_streaming};
 use utils::read_input_file;
 
+type Bag = BTreeMap<String, i64>;
+
 struct Count {
     color: String,
-    count: i32,
+    count: i64,
+}
+
+fn default_bag() -> Bag {
+    Bag::from([
+        ("red".to_owned(), 12),
+        ("green".to_owned(), 13),
+        ("blue".to_owned(), 14),
+    ])
+}
+
+/// Parse a bag definition such as `red=12,green=13,blue=14`.
+/// Entries can be separated by commas or new lines.
+fn parse_bag(input: &str) -> Result<Bag, Error> {
+    let mut bag = Bag::new();
+    for entry in input.split([',', '\n']).map(str::trim) {
+        if entry.is_empty() {
+            continue;
+        }
+        let (color, limit) = entry.split_once('=').ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid bag entry '{entry}', expected color=limit"),
+            )
+        })?;
+        let limit = limit.trim().parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid limit in bag entry '{entry}'"),
+            )
+        })?;
+        bag.insert(color.trim().to_owned(), limit);
+    }
+    Ok(bag)
 }
 
-impl Count {
-    fn default(color: String) -> Self {
-        Count { color, count: 1 }
+/// Load a bag from the CLI argument, either an inline definition or a file path.
+fn load_bag(arg: &str) -> Result<Bag, Error> {
+    match fs::read_to_string(arg) {
+        Ok(content) => parse_bag(&content),
+        Err(_) => parse_bag(arg),
     }
 }
 
-fn extract_game_number(input: &str) -> i32 {
+fn extract_game_number(input: &str) -> i64 {
     let re = Regex::new(r"Game (\d+):").unwrap();
     if let Some(captures) = re.captures(input) {
         if let Some(game_number) = captures.get(1) {
@@ -25,7 +71,7 @@ fn extract_game_number(input: &str) -> i32 {
 
 fn extract_counts(input: &str) -> Vec<Count> {
     let mut counts = Vec::new();
-    let re = Regex::new(r"(\d+) (blue|red|green)").unwrap();
+    let re = Regex::new(r"(\d+) (\w+)").unwrap();
 
     for captures in re.captures_iter(input) {
         let count = captures[1].parse().unwrap();
@@ -36,7 +82,109 @@ fn extract_counts(input: &str) -> Vec<Count> {
     counts
 }
 
-fn sum_of_games(input: String, red: i32, green: i32, blue: i32) -> Result<i32, Error> {
+struct Violation {
+    round: usize,
+    color: String,
+    drawn: i64,
+    limit: i64,
+}
+
+struct GameReport {
+    id: i64,
+    violations: Vec<Violation>,
+    minimal_bag: Bag,
+}
+
+impl GameReport {
+    fn is_possible(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for GameReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.is_possible() {
+            "possible"
+        } else {
+            "impossible"
+        };
+        let minimal_bag: Vec<String> = self
+            .minimal_bag
+            .iter()
+            .map(|(color, count)| format!("{color}={count}"))
+            .collect();
+        writeln!(
+            f,
+            "Game {}: {status} (minimal bag: {})",
+            self.id,
+            minimal_bag.join(",")
+        )?;
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "  round {}: {} {} drawn, limit {}",
+                violation.round, violation.drawn, violation.color, violation.limit
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Game {
+    id: i64,
+    rounds: Vec<Vec<Count>>,
+}
+
+fn parse_game(line: &str) -> Game {
+    let id = extract_game_number(line);
+    let rounds = line.split_once(':').map(|(_, rounds)| rounds).unwrap_or("");
+    Game {
+        id,
+        rounds: rounds.split(';').map(extract_counts).collect(),
+    }
+}
+
+/// Check every round of a game against the bag and keep track of each offending draw.
+fn explain_game(line: &str, bag: &Bag) -> GameReport {
+    let game = parse_game(line);
+
+    let mut violations = Vec::new();
+    for (round, draws) in game.rounds.iter().enumerate() {
+        for item in draws {
+            let limit = bag.get(&item.color).copied().unwrap_or(0);
+            if item.count > limit {
+                violations.push(Violation {
+                    round,
+                    color: item.color.clone(),
+                    drawn: item.count,
+                    limit,
+                });
+            }
+        }
+    }
+
+    let all_counts: Vec<&Count> = game.rounds.iter().flatten().collect();
+    GameReport {
+        id: game.id,
+        violations,
+        minimal_bag: minimal_bag(all_counts),
+    }
+}
+
+fn explain_games(input: String, bag: &Bag) -> Result<Vec<GameReport>, Error> {
+    let reader = read_input_file(input)?;
+
+    let mut reports = Vec::new();
+    for line in reader.lines() {
+        match line {
+            Ok(line_content) => reports.push(explain_game(&line_content, bag)),
+            Err(e) => eprintln!("Error reading line: {}", e),
+        }
+    }
+    Ok(reports)
+}
+
+fn sum_of_games(input: String, bag: &Bag) -> Result<i64, Error> {
     let reader = read_input_file(input)?;
 
     let mut sum = 0;
@@ -47,20 +195,12 @@ fn sum_of_games(input: String, red: i32, green: i32, blue: i32) -> Result<i32, E
                 let game_number = extract_game_number(&line_content);
                 let counts = extract_counts(&line_content);
 
-                let red_arr = counts
-                    .iter()
-                    .filter(|item| item.color == "red")
-                    .find(|item| item.count > red);
-                let green_arr = counts
-                    .iter()
-                    .filter(|item| item.color == "green")
-                    .find(|item| item.count > green);
-                let blue_arr = counts
+                // Colours missing from the bag have a limit of 0
+                let impossible = counts
                     .iter()
-                    .filter(|item| item.color == "blue")
-                    .find(|item| item.count > blue);
+                    .any(|item| item.count > bag.get(&item.color).copied().unwrap_or(0));
 
-                if !(red_arr.is_some() || green_arr.is_some() || blue_arr.is_some()) {
+                if !impossible {
                     sum += game_number;
                 }
             }
@@ -70,7 +210,17 @@ fn sum_of_games(input: String, red: i32, green: i32, blue: i32) -> Result<i32, E
     Ok(sum)
 }
 
-fn power_of_cubes(input: String) -> Result<i32, Error> {
+/// Per-colour maxima drawn in a game, i.e. the smallest bag making it possible.
+fn minimal_bag<'a>(counts: impl IntoIterator<Item = &'a Count>) -> Bag {
+    let mut bag = Bag::new();
+    for item in counts {
+        let max = bag.entry(item.color.clone()).or_insert(0);
+        *max = (*max).max(item.count);
+    }
+    bag
+}
+
+fn power_of_cubes(input: String) -> Result<i64, Error> {
     let reader = read_input_file(input)?;
 
     let mut sum = 0;
@@ -80,27 +230,7 @@ fn power_of_cubes(input: String) -> Result<i32, Error> {
             Ok(line_content) => {
                 let counts = extract_counts(&line_content);
 
-                let default_red = Count::default("red".to_owned());
-                let default_green = Count::default("green".to_owned());
-                let default_blue = Count::default("blue".to_owned());
-
-                let max_red = counts
-                    .iter()
-                    .filter(|item| item.color == "red")
-                    .max_by_key(|item| item.count)
-                    .unwrap_or(&default_red);
-                let max_green = counts
-                    .iter()
-                    .filter(|item| item.color == "green")
-                    .max_by_key(|item| item.count)
-                    .unwrap_or(&default_green);
-                let max_blue = counts
-                    .iter()
-                    .filter(|item| item.color == "blue")
-                    .max_by_key(|item| item.count)
-                    .unwrap_or(&default_blue);
-
-                let result = max_red.count * max_green.count * max_blue.count;
+                let result: i64 = minimal_bag(&counts).values().product();
 
                 sum += result;
             }
@@ -110,13 +240,94 @@ fn power_of_cubes(input: String) -> Result<i32, Error> {
     Ok(sum)
 }
 
+fn parse_arg<T: std::str::FromStr>(args: &[String], idx: usize, default: T) -> Result<T, Error> {
+    match args.get(idx) {
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Invalid argument '{arg}'"))),
+        None => Ok(default),
+    }
+}
+
+fn print_likelihoods(input: String, args: &[String]) -> Result<(), Error> {
+    let game_id: i64 = parse_arg(args, 2, 1)?;
+    let extra = parse_arg(args, 3, 3)?;
+    let top = parse_arg(args, 4, 5)?;
+    let estimation = match args.get(5) {
+        Some(_) => Estimation::MonteCarlo {
+            samples: parse_arg(args, 5, 10_000)?,
+            seed: parse_arg(args, 6, 0)?,
+        },
+        None => Estimation::Exact,
+    };
+
+    let reader = read_input_file(input)?;
+    let game = reader
+        .lines()
+        .map_while(Result::ok)
+        .map(|line| parse_game(&line))
+        .find(|game| game.id == game_id)
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Game {game_id} not found")))?;
+
+    let candidates = candidate_bags(&game, extra);
+    for ranked in most_likely_bags(&game, candidates, &estimation, top) {
+        let bag: Vec<String> = ranked
+            .bag
+            .iter()
+            .map(|(color, count)| format!("{color}={count}"))
+            .collect();
+        println!("{} {:e}", bag.join(","), ranked.likelihood);
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Error> {
     println!("Hello advent of code day 2 !");
 
-    let sum = sum_of_games("day2/src/resources/input.txt".to_owned(), 12, 13, 14)?;
+    let mut args: Vec<String> = env::args().collect();
+    let mode = match args.get(1).map(String::as_str) {
+        Some("explain") | Some("likelihood") | Some("stream") | Some("bench") => {
+            Some(args.remove(1))
+        }
+        _ => None,
+    };
+
+    if mode.as_deref() == Some("bench") {
+        return bench(parse_arg(&args, 1, 10_000)?, &default_bag());
+    }
+
+    let input = args
+        .get(1)
+        .cloned()
+        .unwrap_or("day2/src/resources/input.txt".to_owned());
+
+    if mode.as_deref() == Some("likelihood") {
+        return print_likelihoods(input, &args);
+    }
+
+    let bag = match args.get(2) {
+        Some(arg) => load_bag(arg)?,
+        None => default_bag(),
+    };
+
+    if mode.as_deref() == Some("stream") {
+        let totals = solve_streaming(read_input_file(input)?, &bag)?;
+        println!("{}", totals.possible_sum);
+        println!("{}", totals.power_sum);
+        return Ok(());
+    }
+
+    if mode.as_deref() == Some("explain") {
+        for report in explain_games(input, &bag)? {
+            print!("{report}");
+        }
+        return Ok(());
+    }
+
+    let sum = sum_of_games(input.clone(), &bag)?;
     println!("{sum}");
 
-    let sum = power_of_cubes("day2/src/resources/input.txt".to_owned())?;
+    let sum = power_of_cubes(input)?;
     println!("{sum}");
 
     Ok(())
@@ -124,11 +335,13 @@ fn main() -> Result<(), Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{power_of_cubes, sum_of_games};
+    use crate::{
+        default_bag, explain_games, load_bag, parse_bag, power_of_cubes, sum_of_games, Bag,
+    };
 
     #[test]
     fn should_get_games_sum() -> Result<(), String> {
-        let result = sum_of_games("src/resources/test-input.txt".to_owned(), 12, 13, 14)
+        let result = sum_of_games("src/resources/test-input.txt".to_owned(), &default_bag())
             .map_err(|e| format!("Test failed with error: {:?}", e))?;
         assert_eq!(result, 8);
         Ok(())
@@ -141,4 +354,82 @@ mod tests {
         assert_eq!(result, 2286);
         Ok(())
     }
+
+    #[test]
+    fn should_get_games_sum_with_custom_colors() -> Result<(), String> {
+        let bag = parse_bag("red=12,green=13,blue=14,yellow=5")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let result = sum_of_games("src/resources/test-input-colors.txt".to_owned(), &bag)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn should_load_bag_from_file() -> Result<(), String> {
+        let bag = load_bag("src/resources/test-bag.txt")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let result = sum_of_games("src/resources/test-input-colors.txt".to_owned(), &bag)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_invalid_bag() {
+        assert!(parse_bag("red:12").is_err());
+        assert!(parse_bag("red=many").is_err());
+    }
+
+    #[test]
+    fn shoud_get_power_of_cubes_with_custom_colors() -> Result<(), String> {
+        let result = power_of_cubes("src/resources/test-input-colors.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 24 + 60 + 28);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_get_power_of_cubes_beyond_i32() -> Result<(), String> {
+        let result = power_of_cubes("src/resources/test-input-large.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 10_000_000_000);
+        Ok(())
+    }
+
+    #[test]
+    fn should_explain_games() -> Result<(), String> {
+        let reports = explain_games("src/resources/test-input.txt".to_owned(), &default_bag())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let possible: Vec<i64> = reports
+            .iter()
+            .filter(|report| report.is_possible())
+            .map(|report| report.id)
+            .collect();
+        assert_eq!(possible, vec![1, 2, 5]);
+
+        let game3 = &reports[2];
+        assert_eq!(game3.violations.len(), 1);
+        assert_eq!(game3.violations[0].round, 0);
+        assert_eq!(game3.violations[0].color, "red");
+        assert_eq!(game3.violations[0].drawn, 20);
+        assert_eq!(game3.violations[0].limit, 12);
+
+        let game4 = &reports[3];
+        let offending: Vec<(usize, &str, i64)> = game4
+            .violations
+            .iter()
+            .map(|v| (v.round, v.color.as_str(), v.drawn))
+            .collect();
+        assert_eq!(offending, vec![(2, "blue", 15), (2, "red", 14)]);
+        assert_eq!(
+            game4.minimal_bag,
+            Bag::from([
+                ("red".to_owned(), 14),
+                ("green".to_owned(), 3),
+                ("blue".to_owned(), 15),
+            ])
+        );
+        Ok(())
+    }
 }
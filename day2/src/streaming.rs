@@ -0,0 +1,207 @@
+use crate::{power_of_cubes, sum_of_games, Bag};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Error, Write};
+use std::time::Instant;
+use utils::{read_input_file, SplitMix64};
+
+pub struct Totals {
+    pub possible_sum: i64,
+    pub power_sum: i64,
+}
+
+/// Colours seen so far with their bag limit and the maximum drawn in the current game.
+struct Colors {
+    names: Vec<Vec<u8>>,
+    limits: Vec<i64>,
+    maxima: Vec<i64>,
+}
+
+impl Colors {
+    fn new(bag: &Bag) -> Self {
+        Colors {
+            names: bag.keys().map(|color| color.as_bytes().to_vec()).collect(),
+            limits: bag.values().copied().collect(),
+            maxima: vec![-1; bag.len()],
+        }
+    }
+
+    fn index_of(&mut self, name: &[u8]) -> usize {
+        if let Some(idx) = self.names.iter().position(|known| known == name) {
+            return idx;
+        }
+        // Only happens once per colour missing from the bag, whose limit is 0
+        self.names.push(name.to_vec());
+        self.limits.push(0);
+        self.maxima.push(-1);
+        self.names.len() - 1
+    }
+}
+
+fn parse_number(line: &[u8], pos: &mut usize) -> i64 {
+    let mut value = 0;
+    while *pos < line.len() && line[*pos].is_ascii_digit() {
+        value = value * 10 + (line[*pos] - b'0') as i64;
+        *pos += 1;
+    }
+    value
+}
+
+/// Walk a `Game N: ...` line once, updating the per-colour maxima.
+/// Returns the game number and whether every draw fits in the bag.
+fn scan_line(line: &[u8], colors: &mut Colors) -> (i64, bool) {
+    colors.maxima.fill(-1);
+
+    let mut pos = line
+        .iter()
+        .position(u8::is_ascii_digit)
+        .unwrap_or(line.len());
+    let game_number = parse_number(line, &mut pos);
+
+    let mut possible = true;
+    loop {
+        while pos < line.len() && !line[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        if pos == line.len() {
+            break;
+        }
+        let count = parse_number(line, &mut pos);
+        // Like the `(\d+) (\w+)` pattern, a count only counts when a colour follows it
+        if line.get(pos) != Some(&b' ') {
+            continue;
+        }
+        pos += 1;
+        let start = pos;
+        while pos < line.len() && (line[pos].is_ascii_alphanumeric() || line[pos] == b'_') {
+            pos += 1;
+        }
+        if pos == start {
+            continue;
+        }
+
+        let idx = colors.index_of(&line[start..pos]);
+        if count > colors.limits[idx] {
+            possible = false;
+        }
+        colors.maxima[idx] = colors.maxima[idx].max(count);
+    }
+    (game_number, possible)
+}
+
+/// Compute both parts in a single pass, reusing the same line buffer for the whole file.
+pub fn solve_streaming(mut reader: impl BufRead, bag: &Bag) -> Result<Totals, Error> {
+    let mut colors = Colors::new(bag);
+    let mut totals = Totals {
+        possible_sum: 0,
+        power_sum: 0,
+    };
+
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        if line.iter().any(|c| !c.is_ascii_whitespace()) {
+            let (game_number, possible) = scan_line(&line, &mut colors);
+            if possible {
+                totals.possible_sum += game_number;
+            }
+            totals.power_sum += colors
+                .maxima
+                .iter()
+                .filter(|max| **max >= 0)
+                .copied()
+                .product::<i64>();
+        }
+        line.clear();
+    }
+    Ok(totals)
+}
+
+fn generate_games(path: &str, games: usize) -> Result<(), Error> {
+    let mut rng = SplitMix64(2023);
+    let mut writer = BufWriter::new(File::create(path)?);
+    let colors = ["red", "green", "blue"];
+
+    for game in 1..=games {
+        write!(writer, "Game {game}:")?;
+        let rounds = 1 + rng.below(6);
+        for round in 0..rounds {
+            let separator = if round == 0 { "" } else { ";" };
+            write!(writer, "{separator}")?;
+            let draws = 1 + rng.below(3);
+            for (draw, color) in colors.iter().take(draws).enumerate() {
+                let separator = if draw == 0 { "" } else { "," };
+                write!(writer, "{separator} {} {color}", 1 + rng.below(20))?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Time the regex based two-pass solver against the streaming one on a generated log.
+pub fn bench(games: usize, bag: &Bag) -> Result<(), Error> {
+    let path = std::env::temp_dir().join("day2-bench-input.txt");
+    let path = path.to_string_lossy().into_owned();
+    generate_games(&path, games)?;
+
+    let start = Instant::now();
+    let sum = sum_of_games(path.clone(), bag)?;
+    let power = power_of_cubes(path.clone())?;
+    let two_pass = start.elapsed();
+    println!("two-pass:  {sum} {power} in {two_pass:?}");
+
+    let start = Instant::now();
+    let totals = solve_streaming(read_input_file(path.clone())?, bag)?;
+    let streaming = start.elapsed();
+    println!(
+        "streaming: {} {} in {streaming:?}",
+        totals.possible_sum, totals.power_sum
+    );
+    println!(
+        "speedup: x{:.1}",
+        two_pass.as_secs_f64() / streaming.as_secs_f64()
+    );
+
+    std::fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::solve_streaming;
+    use crate::{default_bag, parse_bag, power_of_cubes, sum_of_games};
+    use utils::read_input_file;
+
+    #[test]
+    fn should_match_two_pass_solver() -> Result<(), String> {
+        for input in [
+            "src/resources/test-input.txt",
+            "src/resources/test-input-truncated.txt",
+            "src/resources/test-input-large.txt",
+            "src/resources/input.txt",
+        ] {
+            let reader =
+                read_input_file(input.to_owned()).map_err(|e| format!("Test failed: {:?}", e))?;
+            let totals = solve_streaming(reader, &default_bag())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            let sum = sum_of_games(input.to_owned(), &default_bag())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            let power = power_of_cubes(input.to_owned())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            assert_eq!(totals.possible_sum, sum);
+            assert_eq!(totals.power_sum, power);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn should_stream_custom_colors() -> Result<(), String> {
+        let bag = parse_bag("red=12,green=13,blue=14,yellow=5")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let reader = read_input_file("src/resources/test-input-colors.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let totals = solve_streaming(reader, &bag)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(totals.possible_sum, 3);
+        assert_eq!(totals.power_sum, 24 + 60 + 28);
+        Ok(())
+    }
+}
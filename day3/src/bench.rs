@@ -0,0 +1,66 @@
+use crate::schematic::Schematic;
+use std::time::Instant;
+use utils::SplitMix64;
+
+/// Deterministic schematic made of random numbers, symbols and empty cells.
+fn generate_schematic(size: usize) -> Vec<String> {
+    let mut rng = SplitMix64(2023);
+
+    (0..size)
+        .map(|_| {
+            let mut line = String::with_capacity(size);
+            while line.len() < size {
+                match rng.below(10) {
+                    0 => line.push(['*', '#', '+', '$', '/'][rng.below(5)]),
+                    1 | 2 => {
+                        line.push_str(&rng.below(1000).to_string());
+                        line.push('.');
+                    }
+                    _ => line.push('.'),
+                }
+            }
+            line.truncate(size);
+            line
+        })
+        .collect()
+}
+
+/// The quadratic scan used before the occupancy grid: every symbol for every number.
+fn naive_part_sum(schematic: &Schematic) -> i32 {
+    schematic
+        .numbers()
+        .filter(|(span, _)| schematic.symbols().any(|(pos, _)| span.is_adjacent(pos)))
+        .map(|(_, value)| value)
+        .sum()
+}
+
+fn indexed_part_sum(schematic: &Schematic) -> i32 {
+    schematic
+        .numbers()
+        .filter(|(span, _)| schematic.adjacent_symbols(span).next().is_some())
+        .map(|(_, value)| value)
+        .sum()
+}
+
+pub fn bench(size: usize) {
+    let schematic = Schematic::from_lines(generate_schematic(size));
+    println!(
+        "{size}x{size} schematic with {} tokens",
+        schematic.tokens.len()
+    );
+
+    let start = Instant::now();
+    let naive = naive_part_sum(&schematic);
+    let naive_time = start.elapsed();
+    println!("naive:   {naive} in {naive_time:?}");
+
+    let start = Instant::now();
+    let indexed = indexed_part_sum(&schematic);
+    let indexed_time = start.elapsed();
+    println!("indexed: {indexed} in {indexed_time:?}");
+
+    println!(
+        "speedup: x{:.1}",
+        naive_time.as_secs_f64() / indexed_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,306 @@
+use crate::schematic::{tokenize_line, Coord, Span, Token};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+/// Schematic keeping the part-number and gear-ratio sums up to date while cells are edited.
+///
+/// Each token contributes to the sums on its own, so an edit only retires and recomputes
+/// the tokens found around the cells whose tokenization changed.
+pub struct IncrementalSchematic {
+    cells: Vec<Vec<char>>,
+    tokens: HashMap<usize, Token>,
+    owners: Vec<Vec<Option<usize>>>,
+    next_id: usize,
+    contributions: HashMap<usize, (i64, i64)>,
+    part_sum: i64,
+    gear_sum: i64,
+}
+
+impl IncrementalSchematic {
+    pub fn new<S: AsRef<str>>(lines: &[S]) -> Self {
+        let cells: Vec<Vec<char>> = lines
+            .iter()
+            .map(|line| line.as_ref().chars().collect())
+            .collect();
+        let owners = cells.iter().map(|row| vec![None; row.len()]).collect();
+        let mut schematic = IncrementalSchematic {
+            cells,
+            tokens: HashMap::new(),
+            owners,
+            next_id: 0,
+            contributions: HashMap::new(),
+            part_sum: 0,
+            gear_sum: 0,
+        };
+
+        for (row, line) in lines.iter().enumerate() {
+            for token in tokenize_line(row as i32, line.as_ref()) {
+                schematic.insert(token);
+            }
+        }
+        let ids: Vec<usize> = schematic.tokens.keys().copied().collect();
+        for id in ids {
+            schematic.refresh(id);
+        }
+        schematic
+    }
+
+    pub fn part_sum(&self) -> i64 {
+        self.part_sum
+    }
+
+    pub fn gear_sum(&self) -> i64 {
+        self.gear_sum
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.cells.iter().map(|row| row.iter().collect()).collect()
+    }
+
+    /// Set a cell to a digit, a symbol or `.`, updating both sums.
+    pub fn set(&mut self, pos: Coord, ch: char) -> Result<(), Error> {
+        if ch.is_whitespace() || ch.is_control() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid schematic cell {ch:?}"),
+            ));
+        }
+        let (row, col) = (pos.x as usize, pos.y as usize);
+        if pos.x < 0 || pos.y < 0 || self.cells.get(row).is_none_or(|line| col >= line.len()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cell ({}, {}) is outside the schematic", pos.x, pos.y),
+            ));
+        }
+        if self.cells[row][col] == ch {
+            return Ok(());
+        }
+
+        // Tokens touching the cell or its row neighbours may merge or split
+        let stale: Vec<usize> = self.tokens_in(pos.x..=pos.x, pos.y - 1..=pos.y + 1);
+        let mut start = (pos.y - 1).max(0);
+        let mut end = (pos.y + 1).min(self.cells[row].len() as i32 - 1);
+        for id in &stale {
+            let (first, last) = self.tokens[id].cells();
+            start = start.min(first.y);
+            end = end.max(last.y);
+        }
+
+        let rows = pos.x - 1..=pos.x + 1;
+        let cols = start - 1..=end + 1;
+        for id in self.tokens_in(rows.clone(), cols.clone()) {
+            self.retire(id);
+        }
+        for id in stale {
+            self.remove(id);
+        }
+
+        self.cells[row][col] = ch;
+        let segment: String = self.cells[row][start as usize..=end as usize]
+            .iter()
+            .collect();
+        for token in tokenize_line(pos.x, &segment) {
+            self.insert(shift(token, start));
+        }
+
+        for id in self.tokens_in(rows, cols) {
+            self.refresh(id);
+        }
+        Ok(())
+    }
+
+    fn token_at(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let row = self.owners.get(x as usize)?;
+        row.get(y as usize).copied().flatten()
+    }
+
+    /// Ids of the tokens with at least one cell in the rectangle.
+    fn tokens_in(
+        &self,
+        rows: std::ops::RangeInclusive<i32>,
+        cols: std::ops::RangeInclusive<i32>,
+    ) -> Vec<usize> {
+        let mut found = Vec::new();
+        for x in rows {
+            for y in cols.clone() {
+                if let Some(id) = self.token_at(x, y) {
+                    found.push(id);
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    fn neighbours(&self, id: usize) -> Vec<&Token> {
+        let (first, last) = self.tokens[&id].cells();
+        self.tokens_in(first.x - 1..=last.x + 1, first.y - 1..=last.y + 1)
+            .into_iter()
+            .filter(|other| *other != id)
+            .map(|other| &self.tokens[&other])
+            .collect()
+    }
+
+    fn insert(&mut self, token: Token) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let (first, last) = token.cells();
+        for y in first.y..=last.y {
+            self.owners[first.x as usize][y as usize] = Some(id);
+        }
+        self.tokens.insert(id, token);
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(token) = self.tokens.remove(&id) {
+            let (first, last) = token.cells();
+            for y in first.y..=last.y {
+                self.owners[first.x as usize][y as usize] = None;
+            }
+        }
+    }
+
+    /// Take the token's contribution out of the sums.
+    fn retire(&mut self, id: usize) {
+        if let Some((part, gear)) = self.contributions.remove(&id) {
+            self.part_sum -= part;
+            self.gear_sum -= gear;
+        }
+    }
+
+    /// Compute the token's contribution from its current neighbourhood and add it to the sums.
+    fn refresh(&mut self, id: usize) {
+        self.retire(id);
+        let neighbours = self.neighbours(id);
+        let contribution = match &self.tokens[&id] {
+            Token::Number { value, .. } => {
+                let is_part = neighbours
+                    .iter()
+                    .any(|token| matches!(token, Token::Symbol { .. }));
+                (if is_part { *value as i64 } else { 0 }, 0)
+            }
+            Token::Symbol { ch, .. } => {
+                let numbers: Vec<i64> = neighbours
+                    .iter()
+                    .filter_map(|token| match token {
+                        Token::Number { value, .. } => Some(*value as i64),
+                        Token::Symbol { .. } => None,
+                    })
+                    .collect();
+                // Same definition as `Rule::gear_ratios`: a `*` next to exactly two numbers
+                let is_gear = *ch == '*' && numbers.len() == 2;
+                (0, if is_gear { numbers.iter().product() } else { 0 })
+            }
+        };
+        self.part_sum += contribution.0;
+        self.gear_sum += contribution.1;
+        self.contributions.insert(id, contribution);
+    }
+}
+
+/// Move a token tokenized from a line segment back to its column in the full line.
+fn shift(token: Token, offset: i32) -> Token {
+    match token {
+        Token::Number { span, value } => Token::Number {
+            span: Span {
+                start: Coord {
+                    x: span.start.x,
+                    y: span.start.y + offset,
+                },
+                end: Coord {
+                    x: span.end.x,
+                    y: span.end.y + offset,
+                },
+            },
+            value,
+        },
+        Token::Symbol { pos, ch } => Token::Symbol {
+            pos: Coord {
+                x: pos.x,
+                y: pos.y + offset,
+            },
+            ch,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IncrementalSchematic;
+    use crate::schematic::Coord;
+    use crate::{extract_numbers_adjacent_to_symbols, gear_ratios};
+    use std::fs;
+    use utils::{read_input_file_as_vec, SplitMix64};
+
+    /// Full recompute of both parts from the current grid, going through the input file.
+    fn recompute(lines: &[String], path: &str) -> (i64, i64) {
+        fs::write(path, lines.join("\n")).unwrap();
+        (
+            extract_numbers_adjacent_to_symbols(path.to_owned()).unwrap(),
+            gear_ratios(path.to_owned()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn should_update_sums_on_edit() {
+        let lines = read_input_file_as_vec("src/resources/test-input.txt".to_owned()).unwrap();
+        let mut schematic = IncrementalSchematic::new(&lines);
+        assert_eq!(schematic.part_sum(), 4361);
+        assert_eq!(schematic.gear_sum(), 467835);
+
+        // Removing the `*` below 467 drops its gear, 467 and 35 are no longer parts
+        schematic.set(Coord { x: 1, y: 3 }, '.').unwrap();
+        assert_eq!(schematic.part_sum(), 4361 - 467 - 35);
+        assert_eq!(schematic.gear_sum(), 467835 - 467 * 35);
+
+        // Merging 467 and 114 into a single number next to a symbol
+        schematic.set(Coord { x: 1, y: 3 }, '*').unwrap();
+        schematic.set(Coord { x: 0, y: 3 }, '1').unwrap();
+        schematic.set(Coord { x: 0, y: 4 }, '0').unwrap();
+        assert_eq!(schematic.lines()[0], "46710114..");
+        assert_eq!(schematic.part_sum(), 4361 - 467 + 46710114);
+
+        assert!(schematic.set(Coord { x: 10, y: 0 }, '1').is_err());
+        assert!(schematic.set(Coord { x: 0, y: 0 }, ' ').is_err());
+    }
+
+    #[test]
+    fn should_match_full_recompute_on_random_edits() {
+        let path = std::env::temp_dir().join("day3-incremental-check.txt");
+        let path = path.to_string_lossy().into_owned();
+        let alphabet = [
+            '.', '.', '.', '.', '1', '2', '5', '9', '0', '*', '*', '#', '$',
+        ];
+
+        let mut rng = SplitMix64(0x5eed);
+        let mut next = move |bound: usize| rng.below(bound);
+
+        for _ in 0..20 {
+            let (rows, cols) = (1 + next(8), 1 + next(8));
+            let lines: Vec<String> = (0..rows)
+                .map(|_| (0..cols).map(|_| alphabet[next(alphabet.len())]).collect())
+                .collect();
+            let mut schematic = IncrementalSchematic::new(&lines);
+
+            for _ in 0..30 {
+                let pos = Coord {
+                    x: next(rows) as i32,
+                    y: next(cols) as i32,
+                };
+                schematic.set(pos, alphabet[next(alphabet.len())]).unwrap();
+                let expected = recompute(&schematic.lines(), &path);
+                assert_eq!(
+                    (schematic.part_sum(), schematic.gear_sum()),
+                    expected,
+                    "{:?}",
+                    schematic.lines()
+                );
+            }
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
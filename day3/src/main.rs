@@ -1,219 +1,101 @@
-use std::io::{BufRead, Error};
-use utils::read_input_file;
-
-#[derive(Debug)]
-struct Coord {
-    x: i32,
-    y: i32,
+mod bench;
+mod incremental;
+mod render;
+mod rules;
+mod schematic;
+
+use incremental::IncrementalSchematic;
+use rules::Rule;
+use schematic::{Coord, Schematic};
+use std::env;
+use std::io::{Error, ErrorKind};
+use utils::read_input_file_as_vec;
+
+fn extract_numbers_adjacent_to_symbols(input: String) -> Result<i64, Error> {
+    let schematic = Schematic::parse(input)?;
+    Ok(Rule::part_numbers().evaluate(&schematic))
 }
 
-#[derive(Debug)]
-struct Number {
-    start: Coord,
-    end: Coord,
-    value: i32,
+fn gear_ratios(input: String) -> Result<i64, Error> {
+    let schematic = Schematic::parse(input)?;
+    Ok(Rule::gear_ratios().evaluate(&schematic))
 }
 
-#[derive(Debug)]
-struct Symbol {
-    coord: Coord,
+/// Parse an edit written as `row,col,char`, e.g. `1,3,.`.
+fn parse_edit(edit: &str) -> Result<(Coord, char), Error> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid edit '{edit}', expected row,col,char"),
+        )
+    };
+    let mut parts = edit.splitn(3, ',');
+    let x = parts
+        .next()
+        .and_then(|x| x.parse().ok())
+        .ok_or_else(invalid)?;
+    let y = parts
+        .next()
+        .and_then(|y| y.parse().ok())
+        .ok_or_else(invalid)?;
+    let mut chars = parts.next().ok_or_else(invalid)?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok((Coord { x, y }, ch)),
+        _ => Err(invalid()),
+    }
 }
 
-fn extract_numbers_adjacent_to_symbols(input: String) -> Result<i32, Error> {
-    let reader = read_input_file(input)?;
-
-    let mut numbers = Vec::<Number>::new();
-    let mut symbols = Vec::<Symbol>::new();
-
-    for (row_idx, line) in reader.lines().enumerate() {
-        let mut start_coord = 0;
-        let mut value = String::new();
-
-        let line = line?;
-        let line_size = line.chars().count();
-
-        for (col_idx, ch) in line.chars().enumerate() {
-            if ch != '.' && !ch.is_numeric() {
-                if !value.is_empty() {
-                    numbers.push(Number {
-                        start: Coord {
-                            x: row_idx as i32,
-                            y: start_coord,
-                        },
-                        end: Coord {
-                            x: row_idx as i32,
-                            y: (col_idx - 1) as i32,
-                        },
-                        value: value.parse::<i32>().unwrap(),
-                    });
-                    value.clear();
-                }
+fn main() -> Result<(), Error> {
+    println!("Hello advent of code day 3 !");
 
-                symbols.push(Symbol {
-                    coord: Coord {
-                        x: row_idx as i32,
-                        y: col_idx as i32,
-                    },
-                })
-            } else if ch.is_numeric() {
-                if value.is_empty() {
-                    start_coord = col_idx as i32;
-                } else if col_idx == line_size - 1 {
-                    value.push(ch);
-                    numbers.push(Number {
-                        start: Coord {
-                            x: row_idx as i32,
-                            y: start_coord,
-                        },
-                        end: Coord {
-                            x: row_idx as i32,
-                            y: col_idx as i32,
-                        },
-                        value: value.parse::<i32>().unwrap(),
-                    });
-                    value.clear();
-                }
-                value.push(ch);
-            } else if !value.is_empty() {
-                numbers.push(Number {
-                    start: Coord {
-                        x: row_idx as i32,
-                        y: start_coord,
-                    },
-                    end: Coord {
-                        x: row_idx as i32,
-                        y: (col_idx - 1) as i32,
-                    },
-                    value: value.parse::<i32>().unwrap(),
-                });
-                value.clear();
-            }
-        }
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "bench") {
+        let size = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(300);
+        bench::bench(size);
+        return Ok(());
     }
-
-    let mut sum = 0;
-    for number in numbers {
-        let found = symbols.iter().find(|symb| {
-            let s_x = symb.coord.x;
-            let s_y = symb.coord.y;
-
-            
-            if number.start.x != s_x
-                && number.start.x != s_x + 1
-                && number.start.x != s_x - 1
-            {
-                false
-            } else {
-                number.start.y - 1 <= s_y && s_y <= number.end.y + 1
-            }
+    if args.get(1).is_some_and(|arg| arg == "render") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day3/src/resources/input.txt".to_owned());
+        let output = args.get(3).cloned().unwrap_or_else(|| {
+            let path = std::env::temp_dir().join("day3-schematic.html");
+            path.to_string_lossy().into_owned()
         });
-
-        if found.is_some() {
-            sum += number.value;
-        }
+        let lines = read_input_file_as_vec(input)?;
+        let schematic = Schematic::from_lines(&lines);
+        print!("{}", render::render_terminal(&lines, &schematic));
+        render::write_html(&output, &lines, &schematic)?;
+        println!("HTML written to {output}");
+        return Ok(());
     }
-
-    Ok(sum)
-}
-
-fn gear_ratios(input: String) -> Result<i32, Error> {
-    let reader = read_input_file(input)?;
-
-    let mut numbers = Vec::<Number>::new();
-    let mut gears = Vec::<Symbol>::new();
-
-    for (row_idx, line) in reader.lines().enumerate() {
-        let mut start_coord = 0;
-        let mut value = String::new();
-
-        let line = line?;
-        let line_size = line.chars().count();
-
-        for (col_idx, ch) in line.chars().enumerate() {
-            if ch == '*' {
-                if !value.is_empty() {
-                    numbers.push(Number {
-                        start: Coord {
-                            x: row_idx as i32,
-                            y: start_coord,
-                        },
-                        end: Coord {
-                            x: row_idx as i32,
-                            y: (col_idx - 1) as i32,
-                        },
-                        value: value.parse::<i32>().unwrap(),
-                    });
-                    value.clear();
-                }
-
-                gears.push(Symbol {
-                    coord: Coord {
-                        x: row_idx as i32,
-                        y: col_idx as i32,
-                    },
-                })
-            } else if ch.is_numeric() {
-                if value.is_empty() {
-                    start_coord = col_idx as i32;
-                } else if col_idx == line_size - 1 {
-                    value.push(ch);
-                    numbers.push(Number {
-                        start: Coord {
-                            x: row_idx as i32,
-                            y: start_coord,
-                        },
-                        end: Coord {
-                            x: row_idx as i32,
-                            y: col_idx as i32,
-                        },
-                        value: value.parse::<i32>().unwrap(),
-                    });
-                    value.clear();
-                }
-                value.push(ch);
-            } else if !value.is_empty() {
-                numbers.push(Number {
-                    start: Coord {
-                        x: row_idx as i32,
-                        y: start_coord,
-                    },
-                    end: Coord {
-                        x: row_idx as i32,
-                        y: (col_idx - 1) as i32,
-                    },
-                    value: value.parse::<i32>().unwrap(),
-                });
-                value.clear();
-            }
+    if args.get(1).is_some_and(|arg| arg == "edit") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day3/src/resources/input.txt".to_owned());
+        let mut schematic = IncrementalSchematic::new(&read_input_file_as_vec(input)?);
+        println!("{} {}", schematic.part_sum(), schematic.gear_sum());
+        for edit in args.iter().skip(3) {
+            let (pos, ch) = parse_edit(edit)?;
+            schematic.set(pos, ch)?;
+            println!("{edit}: {} {}", schematic.part_sum(), schematic.gear_sum());
         }
-    }
-
-    let mut sum = 0;
-    for gear in gears {
-        let nums: Vec<&Number> = numbers
-            .iter()
-            .filter(|num| {
-                let s_x = gear.coord.x;
-                let s_y = gear.coord.y;
-
-                if num.start.x != s_x && num.start.x != s_x + 1 && num.start.x != s_x - 1 {
-                    false
-                } else { num.start.y - 1 <= s_y && s_y <= num.end.y + 1 }
-            })
-            .collect();
-
-        if nums.len() > 1 {
-            let result: i32 = nums.iter().map(|num| num.value).product();
-
-            sum += result;
+        for line in schematic.lines() {
+            println!("{line}");
         }
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "rule") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day3/src/resources/input.txt".to_owned());
+        let rule = Rule::from_args(args.get(3..).unwrap_or_default())?;
+        println!("The result is {}", rule.evaluate(&Schematic::parse(input)?));
+        return Ok(());
     }
-
-    Ok(sum)
-}
-
-fn main() -> Result<(), Error> {
-    println!("Hello advent of code day 3 !");
 
     let result = extract_numbers_adjacent_to_symbols("day3/src/resources/input.txt".to_owned())?;
 
@@ -245,4 +127,16 @@ mod tests {
         assert_eq!(result, 467835);
         Ok(())
     }
+
+    #[test]
+    fn should_keep_numbers_on_edges() -> Result<(), String> {
+        let result =
+            extract_numbers_adjacent_to_symbols("src/resources/test-input-edges.txt".to_owned())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 1 + 7 + 42 + 5 + 3);
+        let result = gear_ratios("src/resources/test-input-edges.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 42 * 5);
+        Ok(())
+    }
 }
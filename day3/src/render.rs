@@ -0,0 +1,209 @@
+use crate::rules::Rule;
+use crate::schematic::{Coord, Schematic, Token};
+use colored::*;
+use std::fs;
+use std::io::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Part,
+    NotPart,
+    Symbol,
+    Gear,
+}
+
+impl Class {
+    fn css(&self) -> &'static str {
+        match self {
+            Class::Part => "part",
+            Class::NotPart => "not-part",
+            Class::Symbol => "symbol",
+            Class::Gear => "gear",
+        }
+    }
+}
+
+fn classify(schematic: &Schematic) -> Vec<Class> {
+    let gears = Rule::gear_ratios();
+    schematic
+        .tokens
+        .iter()
+        .map(|token| match token {
+            Token::Number { span, .. } => {
+                if schematic.adjacent_symbols(span).next().is_some() {
+                    Class::Part
+                } else {
+                    Class::NotPart
+                }
+            }
+            Token::Symbol { pos, ch } => {
+                if gears.matches(schematic, pos, *ch).is_some() {
+                    Class::Gear
+                } else {
+                    Class::Symbol
+                }
+            }
+        })
+        .collect()
+}
+
+/// Print the schematic with each token coloured according to how it was counted.
+pub fn render_terminal(lines: &[String], schematic: &Schematic) -> String {
+    let classes = classify(schematic);
+    let mut output = String::new();
+
+    for (row, line) in lines.iter().enumerate() {
+        for (col, ch) in line.chars().enumerate() {
+            let pos = Coord {
+                x: row as i32,
+                y: col as i32,
+            };
+            let cell = ch.to_string();
+            let cell = match schematic.token_at(&pos).map(|idx| classes[idx]) {
+                Some(Class::Part) => cell.green(),
+                Some(Class::NotPart) => cell.red(),
+                Some(Class::Symbol) => cell.cyan(),
+                Some(Class::Gear) => cell.yellow().bold(),
+                None => cell.bright_black(),
+            };
+            output.push_str(&cell.to_string());
+        }
+        output.push('\n');
+    }
+
+    output.push_str(&format!(
+        "{} {} {} {}\n",
+        "part number".green(),
+        "not a part number".red(),
+        "symbol".cyan(),
+        "gear".yellow().bold()
+    ));
+    output
+}
+
+fn escape(ch: char) -> String {
+    match ch {
+        '&' => "&amp;".to_owned(),
+        '<' => "&lt;".to_owned(),
+        '>' => "&gt;".to_owned(),
+        '"' => "&quot;".to_owned(),
+        ch => ch.to_string(),
+    }
+}
+
+/// Hover text of a token, numbers listing the symbols they touch.
+fn tooltip(schematic: &Schematic, token: &Token) -> String {
+    match token {
+        Token::Number { span, value } => {
+            let symbols: Vec<String> = schematic
+                .adjacent_symbols(span)
+                .map(|(pos, ch)| format!("{} at ({}, {})", escape(ch), pos.x, pos.y))
+                .collect();
+            if symbols.is_empty() {
+                format!("{value}: no adjacent symbol")
+            } else {
+                format!("{value}: adjacent to {}", symbols.join(", "))
+            }
+        }
+        Token::Symbol { pos, ch } => format!("{} at ({}, {})", escape(*ch), pos.x, pos.y),
+    }
+}
+
+/// Standalone HTML page of the schematic, hovering a number shows its adjacent symbols.
+pub fn render_html(lines: &[String], schematic: &Schematic) -> String {
+    let classes = classify(schematic);
+    let mut body = String::new();
+
+    for (row, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut col = 0;
+        while col < chars.len() {
+            let pos = Coord {
+                x: row as i32,
+                y: col as i32,
+            };
+            let Some(idx) = schematic.token_at(&pos) else {
+                body.push_str(&escape(chars[col]));
+                col += 1;
+                continue;
+            };
+
+            let token = &schematic.tokens[idx];
+            let end = match token {
+                Token::Number { span, .. } => span.end.y as usize,
+                Token::Symbol { .. } => col,
+            };
+            let text: String = chars[col..=end].iter().map(|ch| escape(*ch)).collect();
+            body.push_str(&format!(
+                "<span class=\"{}\" title=\"{}\">{text}</span>",
+                classes[idx].css(),
+                tooltip(schematic, token)
+            ));
+            col = end + 1;
+        }
+        body.push('\n');
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Engine schematic</title>
+<style>
+body {{ background: #0f0f23; color: #666; font-family: monospace; }}
+.part {{ color: #00cc00; }}
+.not-part {{ color: #ff4444; }}
+.symbol {{ color: #33cccc; }}
+.gear {{ color: #ffff66; font-weight: bold; }}
+span:hover {{ background: #333; }}
+</style>
+</head>
+<body>
+<p><span class="part">part number</span> <span class="not-part">not a part number</span> <span class="symbol">symbol</span> <span class="gear">gear</span></p>
+<pre>
+{body}</pre>
+</body>
+</html>
+"#
+    )
+}
+
+pub fn write_html(path: &str, lines: &[String], schematic: &Schematic) -> Result<(), Error> {
+    fs::write(path, render_html(lines, schematic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_html, render_terminal};
+    use crate::schematic::Schematic;
+
+    fn lines() -> Vec<String> {
+        ["467..114..", "...*......", "..35..633."]
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn should_render_plain_schematic_without_colors() {
+        colored::control::set_override(false);
+        let lines = lines();
+        let rendered = render_terminal(&lines, &Schematic::from_lines(&lines));
+        colored::control::unset_override();
+        assert!(rendered.starts_with("467..114..\n...*......\n..35..633.\n"));
+    }
+
+    #[test]
+    fn should_render_html_tooltips() {
+        let lines = lines();
+        let html = render_html(&lines, &Schematic::from_lines(&lines));
+        assert!(
+            html.contains(r#"<span class="part" title="467: adjacent to * at (1, 3)">467</span>"#)
+        );
+        assert!(
+            html.contains(r#"<span class="not-part" title="114: no adjacent symbol">114</span>"#)
+        );
+        assert!(html.contains(r#"<span class="gear" title="* at (1, 3)">*</span>"#));
+    }
+}
@@ -0,0 +1,311 @@
+use crate::schematic::{Coord, Schematic, Span};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+fn invalid(kind: &str, value: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("Invalid {kind} '{value}'"))
+}
+
+pub enum SymbolSet {
+    Any,
+    Only(Vec<char>),
+}
+
+impl SymbolSet {
+    fn contains(&self, ch: char) -> bool {
+        match self {
+            SymbolSet::Any => true,
+            SymbolSet::Only(chars) => chars.contains(&ch),
+        }
+    }
+}
+
+impl FromStr for SymbolSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(SymbolSet::Any),
+            "" => Err(invalid("symbol set", s)),
+            chars => Ok(SymbolSet::Only(chars.chars().collect())),
+        }
+    }
+}
+
+/// Number of adjacent numbers a symbol needs to be taken into account.
+pub enum Required {
+    Exact(usize),
+    Min(usize),
+    Max(usize),
+}
+
+impl Required {
+    fn accepts(&self, count: usize) -> bool {
+        match self {
+            Required::Exact(n) => count == *n,
+            Required::Min(n) => count >= *n,
+            Required::Max(n) => count <= *n,
+        }
+    }
+}
+
+impl FromStr for Required {
+    type Err = Error;
+
+    /// Parse `exact:N`, `min:N` or `max:N`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, count) = s.split_once(':').ok_or_else(|| invalid("count", s))?;
+        let count = count.parse().map_err(|_| invalid("count", s))?;
+        match kind {
+            "exact" => Ok(Required::Exact(count)),
+            "min" => Ok(Required::Min(count)),
+            "max" => Ok(Required::Max(count)),
+            _ => Err(invalid("count", s)),
+        }
+    }
+}
+
+pub enum Aggregation {
+    Sum,
+    Product,
+    Max,
+}
+
+impl Aggregation {
+    /// Combine the values, an empty list being worth nothing.
+    fn apply(&self, values: impl Iterator<Item = i64>) -> i64 {
+        let mut values = values.peekable();
+        if values.peek().is_none() {
+            return 0;
+        }
+        match self {
+            Aggregation::Sum => values.sum(),
+            Aggregation::Product => values.product(),
+            Aggregation::Max => values.max().unwrap_or(0),
+        }
+    }
+}
+
+impl FromStr for Aggregation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Aggregation::Sum),
+            "product" => Ok(Aggregation::Product),
+            "max" => Ok(Aggregation::Max),
+            _ => Err(invalid("aggregation", s)),
+        }
+    }
+}
+
+pub enum Neighbourhood {
+    /// Only cells sharing an edge with the number.
+    Four,
+    /// Diagonals included, as in the puzzle.
+    Eight,
+}
+
+impl Neighbourhood {
+    fn is_adjacent(&self, span: &Span, pos: &Coord) -> bool {
+        match self {
+            Neighbourhood::Eight => span.is_adjacent(pos),
+            Neighbourhood::Four => {
+                let same_row =
+                    pos.x == span.start.x && (pos.y == span.start.y - 1 || pos.y == span.end.y + 1);
+                let next_row = (pos.x - span.start.x).abs() == 1
+                    && span.start.y <= pos.y
+                    && pos.y <= span.end.y;
+                same_row || next_row
+            }
+        }
+    }
+}
+
+impl FromStr for Neighbourhood {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" => Ok(Neighbourhood::Four),
+            "8" => Ok(Neighbourhood::Eight),
+            _ => Err(invalid("neighbourhood", s)),
+        }
+    }
+}
+
+pub enum Scope {
+    /// Aggregate the numbers of each symbol on its own, then add up the results.
+    PerSymbol,
+    /// Aggregate once every number adjacent to at least one matching symbol.
+    Union,
+}
+
+impl FromStr for Scope {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "per-symbol" => Ok(Scope::PerSymbol),
+            "union" => Ok(Scope::Union),
+            _ => Err(invalid("scope", s)),
+        }
+    }
+}
+
+pub struct Rule {
+    pub symbols: SymbolSet,
+    pub required: Required,
+    pub aggregation: Aggregation,
+    pub neighbourhood: Neighbourhood,
+    pub scope: Scope,
+}
+
+impl Rule {
+    /// Part 1: every number next to any symbol, each counted once.
+    pub fn part_numbers() -> Self {
+        Rule {
+            symbols: SymbolSet::Any,
+            required: Required::Min(1),
+            aggregation: Aggregation::Sum,
+            neighbourhood: Neighbourhood::Eight,
+            scope: Scope::Union,
+        }
+    }
+
+    /// Part 2: a `*` next to exactly two numbers is a gear, its ratio being their product.
+    pub fn gear_ratios() -> Self {
+        Rule {
+            symbols: SymbolSet::Only(vec!['*']),
+            required: Required::Exact(2),
+            aggregation: Aggregation::Product,
+            neighbourhood: Neighbourhood::Eight,
+            scope: Scope::PerSymbol,
+        }
+    }
+
+    /// Build a rule from `<symbols> <count> <aggregation> <neighbourhood> <scope>` arguments,
+    /// e.g. `* exact:2 product 8 per-symbol` for the gear ratios.
+    pub fn from_args(args: &[String]) -> Result<Self, Error> {
+        let [symbols, required, aggregation, neighbourhood, scope] = args else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Expected <symbols> <count> <aggregation> <neighbourhood> <scope>",
+            ));
+        };
+        Ok(Rule {
+            symbols: symbols.parse()?,
+            required: required.parse()?,
+            aggregation: aggregation.parse()?,
+            neighbourhood: neighbourhood.parse()?,
+            scope: scope.parse()?,
+        })
+    }
+
+    /// Numbers adjacent to the symbol, or `None` if the symbol doesn't satisfy the rule.
+    pub fn matches<'a>(
+        &self,
+        schematic: &'a Schematic,
+        pos: &Coord,
+        ch: char,
+    ) -> Option<Vec<(&'a Span, i32)>> {
+        if !self.symbols.contains(ch) {
+            return None;
+        }
+        let numbers: Vec<(&Span, i32)> = schematic
+            .adjacent_numbers(pos)
+            .filter(|(span, _)| self.neighbourhood.is_adjacent(span, pos))
+            .collect();
+        self.required.accepts(numbers.len()).then_some(numbers)
+    }
+
+    pub fn evaluate(&self, schematic: &Schematic) -> i64 {
+        let matches = schematic
+            .symbols()
+            .filter_map(|(pos, ch)| self.matches(schematic, pos, ch));
+
+        match self.scope {
+            Scope::PerSymbol => matches
+                .map(|numbers| {
+                    self.aggregation
+                        .apply(numbers.iter().map(|(_, value)| *value as i64))
+                })
+                .sum(),
+            Scope::Union => {
+                // Numbers are keyed by their first cell so they are only counted once
+                let union: BTreeMap<Coord, i32> = matches
+                    .flatten()
+                    .map(|(span, value)| (span.start, value))
+                    .collect();
+                self.aggregation
+                    .apply(union.values().map(|value| *value as i64))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aggregation, Neighbourhood, Required, Rule, Scope, SymbolSet};
+    use crate::schematic::Schematic;
+
+    #[test]
+    fn should_require_exactly_two_numbers_for_gears() {
+        let schematic = Schematic::from_lines(["2.3", ".*.", "..4", "...", "5*6"]);
+        assert_eq!(Rule::gear_ratios().evaluate(&schematic), 5 * 6);
+
+        let rule = Rule {
+            required: Required::Min(2),
+            ..Rule::gear_ratios()
+        };
+        assert_eq!(rule.evaluate(&schematic), 2 * 3 * 4 + 5 * 6);
+    }
+
+    #[test]
+    fn should_use_four_neighbourhood() {
+        let schematic = Schematic::from_lines(["1.2", ".#.", ".3.", "4#5"]);
+        let rule = Rule {
+            neighbourhood: Neighbourhood::Four,
+            ..Rule::part_numbers()
+        };
+        assert_eq!(rule.evaluate(&schematic), 3 + 4 + 5);
+        assert_eq!(Rule::part_numbers().evaluate(&schematic), 1 + 2 + 3 + 4 + 5);
+    }
+
+    #[test]
+    fn should_aggregate_per_symbol_or_union() {
+        let schematic = Schematic::from_lines(["7$.", "..%", "..9"]);
+        let rule = Rule {
+            symbols: SymbolSet::Only(vec!['$', '%']),
+            required: Required::Max(2),
+            aggregation: Aggregation::Max,
+            neighbourhood: Neighbourhood::Eight,
+            scope: Scope::PerSymbol,
+        };
+        assert_eq!(rule.evaluate(&schematic), 7 + 9);
+
+        let rule = Rule {
+            scope: Scope::Union,
+            aggregation: Aggregation::Sum,
+            ..rule
+        };
+        assert_eq!(rule.evaluate(&schematic), 7 + 9);
+    }
+
+    #[test]
+    fn should_build_rule_from_args() {
+        let args: Vec<String> = ["*", "exact:2", "product", "8", "per-symbol"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let schematic = Schematic::from_lines(["2.3", ".*.", "..4", "...", "5*6"]);
+        let rule = Rule::from_args(&args).unwrap();
+        assert_eq!(rule.evaluate(&schematic), 5 * 6);
+
+        assert!(Rule::from_args(&args[..4]).is_err());
+        let mut args = args;
+        args[1] = "few:2".to_owned();
+        assert!(Rule::from_args(&args).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::Error;
+use utils::read_input_file_as_vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Coord {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Cells covered by a number, `start` and `end` being on the same row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Coord,
+    pub end: Coord,
+}
+
+impl Span {
+    pub fn is_adjacent(&self, pos: &Coord) -> bool {
+        (self.start.x - pos.x).abs() <= 1 && self.start.y - 1 <= pos.y && pos.y <= self.end.y + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Number { span: Span, value: i32 },
+    Symbol { pos: Coord, ch: char },
+}
+
+impl Token {
+    /// Cells covered by the token, as the first and last coordinates of its row span.
+    pub(crate) fn cells(&self) -> (Coord, Coord) {
+        match self {
+            Token::Number { span, .. } => (span.start, span.end),
+            Token::Symbol { pos, .. } => (*pos, *pos),
+        }
+    }
+}
+
+fn number(row: i32, start: i32, end: i32, value: i32) -> Token {
+    Token::Number {
+        span: Span {
+            start: Coord { x: row, y: start },
+            end: Coord { x: row, y: end },
+        },
+        value,
+    }
+}
+
+/// Split a schematic line into numbers and symbols, `.` being empty space.
+pub fn tokenize_line(row: i32, line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<(i32, i32)> = None;
+
+    for (col, ch) in line.chars().enumerate() {
+        let col = col as i32;
+        if let Some(digit) = ch.to_digit(10) {
+            let (start, value) = current.unwrap_or((col, 0));
+            current = Some((start, value * 10 + digit as i32));
+            continue;
+        }
+
+        if let Some((start, value)) = current.take() {
+            tokens.push(number(row, start, col - 1, value));
+        }
+        if ch != '.' {
+            tokens.push(Token::Symbol {
+                pos: Coord { x: row, y: col },
+                ch,
+            });
+        }
+    }
+
+    // A number running up to the last column is only flushed once the line is over
+    if let Some((start, value)) = current {
+        let end = line.chars().count() as i32 - 1;
+        tokens.push(number(row, start, end, value));
+    }
+
+    tokens
+}
+
+pub struct Schematic {
+    pub tokens: Vec<Token>,
+    /// Occupancy grid giving the token index found on each cell.
+    grid: Vec<Vec<Option<usize>>>,
+}
+
+impl Schematic {
+    pub fn from_lines<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Self {
+        let tokens = lines
+            .into_iter()
+            .enumerate()
+            .flat_map(|(row, line)| tokenize_line(row as i32, line.as_ref()))
+            .collect();
+        Schematic::from_tokens(tokens)
+    }
+
+    pub fn from_tokens(tokens: Vec<Token>) -> Self {
+        let mut grid: Vec<Vec<Option<usize>>> = Vec::new();
+        for (idx, token) in tokens.iter().enumerate() {
+            let (start, end) = token.cells();
+            let row = start.x as usize;
+            if grid.len() <= row {
+                grid.resize(row + 1, Vec::new());
+            }
+            if grid[row].len() <= end.y as usize {
+                grid[row].resize(end.y as usize + 1, None);
+            }
+            for col in start.y..=end.y {
+                grid[row][col as usize] = Some(idx);
+            }
+        }
+        Schematic { tokens, grid }
+    }
+
+    pub fn token_at(&self, pos: &Coord) -> Option<usize> {
+        if pos.x < 0 || pos.y < 0 {
+            return None;
+        }
+        let row = self.grid.get(pos.x as usize)?;
+        row.get(pos.y as usize).copied().flatten()
+    }
+
+    /// Indexes of the tokens found in the bounding box around the cells, the cells excluded.
+    fn around(&self, start: &Coord, end: &Coord) -> Vec<usize> {
+        let own = self.token_at(start);
+        let mut found = Vec::new();
+        for x in start.x - 1..=end.x + 1 {
+            for y in start.y - 1..=end.y + 1 {
+                if let Some(idx) = self.token_at(&Coord { x, y }) {
+                    if Some(idx) != own {
+                        found.push(idx);
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    pub fn adjacent_symbols(&self, span: &Span) -> impl Iterator<Item = (&Coord, char)> {
+        self.around(&span.start, &span.end)
+            .into_iter()
+            .filter_map(|idx| match &self.tokens[idx] {
+                Token::Symbol { pos, ch } => Some((pos, *ch)),
+                Token::Number { .. } => None,
+            })
+    }
+
+    pub fn adjacent_numbers(&self, pos: &Coord) -> impl Iterator<Item = (&Span, i32)> {
+        self.around(pos, pos)
+            .into_iter()
+            .filter_map(|idx| match &self.tokens[idx] {
+                Token::Number { span, value } => Some((span, *value)),
+                Token::Symbol { .. } => None,
+            })
+    }
+
+    pub fn parse(input: String) -> Result<Self, Error> {
+        Ok(Schematic::from_lines(read_input_file_as_vec(input)?))
+    }
+
+    pub fn numbers(&self) -> impl Iterator<Item = (&Span, i32)> {
+        self.tokens.iter().filter_map(|token| match token {
+            Token::Number { span, value } => Some((span, *value)),
+            Token::Symbol { .. } => None,
+        })
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = (&Coord, char)> {
+        self.tokens.iter().filter_map(|token| match token {
+            Token::Symbol { pos, ch } => Some((pos, *ch)),
+            Token::Number { .. } => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{number, tokenize_line, Coord, Schematic, Token};
+
+    #[test]
+    fn should_tokenize_number_at_line_start() {
+        let tokens = tokenize_line(0, "42..*");
+        assert_eq!(
+            tokens,
+            vec![
+                number(0, 0, 1, 42),
+                Token::Symbol {
+                    pos: Coord { x: 0, y: 4 },
+                    ch: '*'
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn should_tokenize_numbers_at_line_end() {
+        assert_eq!(
+            tokenize_line(2, "..#7"),
+            vec![
+                Token::Symbol {
+                    pos: Coord { x: 2, y: 2 },
+                    ch: '#'
+                },
+                number(2, 3, 3, 7)
+            ]
+        );
+        assert_eq!(tokenize_line(0, "...123"), vec![number(0, 3, 5, 123)]);
+    }
+
+    #[test]
+    fn should_tokenize_numbers_touching_symbols_and_edges() {
+        let tokens = tokenize_line(1, "1*2$34");
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0], number(1, 0, 0, 1));
+        assert_eq!(tokens[2], number(1, 2, 2, 2));
+        assert_eq!(tokens[4], number(1, 4, 5, 34));
+        assert_eq!(tokenize_line(0, "5"), vec![number(0, 0, 0, 5)]);
+    }
+
+    #[test]
+    fn should_index_adjacent_tokens() {
+        let schematic = Schematic::from_lines(["467..114..", "...*......", "..35..633."]);
+        let gear = Coord { x: 1, y: 3 };
+        let values: Vec<i32> = schematic
+            .adjacent_numbers(&gear)
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(values, vec![467, 35]);
+
+        let (span, _) = schematic
+            .numbers()
+            .find(|(_, value)| *value == 114)
+            .unwrap();
+        assert_eq!(schematic.adjacent_symbols(span).count(), 0);
+        assert_eq!(schematic.token_at(&Coord { x: 2, y: 7 }), Some(4));
+        assert_eq!(schematic.token_at(&Coord { x: 5, y: 0 }), None);
+    }
+}
@@ -1,57 +1,314 @@
+mod streaming;
+
 use regex::Regex;
-use std::io::{BufRead, Error};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::io::{BufRead, Error, ErrorKind};
+use streaming::process_streaming;
 use utils::read_input_file;
 
-fn extract_arrays(line: String) -> (Vec<i32>, Vec<i32>) {
-    let re = Regex::new(r"Card\s*\d+: (.*?) \| (.*)").unwrap();
-    let (result, winning) = if let Some(captures) = re.captures(&line) {
-        let winning = captures[1]
-            .split_whitespace()
-            .flat_map(str::parse)
-            .collect();
-        let result = captures[2]
-            .split_whitespace()
-            .flat_map(str::parse)
-            .collect();
-        (winning, result)
-    } else {
-        (Vec::<i32>::new(), Vec::<i32>::new())
-    };
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Scratchcard {
+    id: u32,
+    winning: Vec<u32>,
+    have: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseMode {
+    /// Any issue makes the whole input invalid.
+    Strict,
+    /// Issues are worked around and reported alongside the cards.
+    Lenient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Winning,
+    Have,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Winning => write!(f, "winning"),
+            Side::Have => write!(f, "have"),
+        }
+    }
+}
 
-    (result, winning)
+/// Problem found while parsing a card, and what lenient mode does about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Issue {
+    /// The line doesn't look like `Card N: ... | ...`, lenient mode skips it.
+    MalformedCard(String),
+    /// A token that isn't a number, lenient mode drops it.
+    InvalidNumber { side: Side, token: String },
+    /// A number listed twice on the same side, lenient mode keeps a single one.
+    DuplicateNumber { side: Side, number: u32 },
+    /// A side whose length differs from the first card, lenient mode keeps it as is.
+    SideLength {
+        side: Side,
+        expected: usize,
+        got: usize,
+    },
+    /// A card id skipped before this one, lenient mode stands in a card without numbers.
+    MissingCard(u32),
+    /// A card id repeated or lower than the previous one, lenient mode skips the card.
+    OutOfOrderCard { id: u32, previous: u32 },
 }
 
-fn process(input: String) -> Result<(i32, i32), Error> {
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MalformedCard(line) => write!(f, "malformed card '{line}'"),
+            Issue::InvalidNumber { side, token } => {
+                write!(f, "invalid number '{token}' on the {side} side")
+            }
+            Issue::DuplicateNumber { side, number } => {
+                write!(f, "duplicate number {number} on the {side} side")
+            }
+            Issue::SideLength {
+                side,
+                expected,
+                got,
+            } => write!(f, "{side} side has {got} numbers, expected {expected}"),
+            Issue::MissingCard(id) => write!(f, "card {id} is missing"),
+            Issue::OutOfOrderCard { id, previous } => {
+                write!(f, "card {id} comes after card {previous}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Report {
+    line: usize,
+    issue: Issue,
+}
+
+fn parse_side(numbers: &str, side: Side, issues: &mut Vec<Issue>) -> Vec<u32> {
+    let mut parsed = Vec::new();
+    for token in numbers.split_whitespace() {
+        match token.parse() {
+            Ok(number) if parsed.contains(&number) => {
+                issues.push(Issue::DuplicateNumber { side, number })
+            }
+            Ok(number) => parsed.push(number),
+            Err(_) => issues.push(Issue::InvalidNumber {
+                side,
+                token: token.to_owned(),
+            }),
+        }
+    }
+    parsed
+}
+
+impl Scratchcard {
+    /// Parse a card, dropping invalid tokens and duplicates and returning them as issues.
+    fn parse(line: &str) -> Result<(Self, Vec<Issue>), Issue> {
+        let re = Regex::new(r"^Card\s*(\d+): (.*?) \| (.*)$").unwrap();
+        let captures = re
+            .captures(line)
+            .ok_or_else(|| Issue::MalformedCard(line.to_owned()))?;
+        let id = captures[1]
+            .parse()
+            .map_err(|_| Issue::MalformedCard(line.to_owned()))?;
+
+        let mut issues = Vec::new();
+        let winning = parse_side(&captures[2], Side::Winning, &mut issues);
+        let have = parse_side(&captures[3], Side::Have, &mut issues);
+        Ok((Scratchcard { id, winning, have }, issues))
+    }
+
+    fn matches(&self) -> usize {
+        self.have
+            .iter()
+            .filter(|num| self.winning.contains(num))
+            .count()
+    }
+
+    fn score(&self) -> Result<u64, Error> {
+        match self.matches() {
+            0 => Ok(0),
+            matches => 1u64.checked_shl(matches as u32 - 1).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Card {} has {matches} matches, its score doesn't fit in 64 bits",
+                        self.id
+                    ),
+                )
+            }),
+        }
+    }
+}
+
+/// Sum of the card scores, failing instead of overflowing.
+fn total_score(cards: &[Scratchcard]) -> Result<u64, Error> {
+    cards.iter().try_fold(0u64, |sum, card| {
+        sum.checked_add(card.score()?)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Total score doesn't fit in 64 bits"))
+    })
+}
+
+/// Fail on the issue in strict mode, keep track of it in lenient mode.
+fn record(
+    mode: ParseMode,
+    reports: &mut Vec<Report>,
+    line: usize,
+    issue: Issue,
+) -> Result<(), Error> {
+    match mode {
+        ParseMode::Strict => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Line {line}: {issue}"),
+        )),
+        ParseMode::Lenient => {
+            reports.push(Report { line, issue });
+            Ok(())
+        }
+    }
+}
+
+fn read_cards(input: String, mode: ParseMode) -> Result<(Vec<Scratchcard>, Vec<Report>), Error> {
     let reader = read_input_file(input)?;
-    let mut sum = 0;
-    let mut cards = vec![0i32; reader.capacity()];
+    let mut cards: Vec<Scratchcard> = Vec::new();
+    let mut reports = Vec::new();
 
     for (idx, line) in reader.lines().enumerate() {
+        let line_number = idx + 1;
         match line {
-            Ok(line_content) => {
-                cards[idx] += 1;
-                let (winning, result) = extract_arrays(line_content);
-                let matched = result
-                    .iter()
-                    .filter(|num| winning.iter().any(|win| &win == num));
-
-                for v in 1..matched.clone().count() + 1 {
-                    cards[idx + v] += cards[idx];
+            Ok(line_content) if line_content.trim().is_empty() => continue,
+            Ok(line_content) => match Scratchcard::parse(&line_content) {
+                Ok((card, issues)) => {
+                    if let Some(previous) = cards.last().map(|last| last.id) {
+                        if card.id <= previous {
+                            let issue = Issue::OutOfOrderCard {
+                                id: card.id,
+                                previous,
+                            };
+                            record(mode, &mut reports, line_number, issue)?;
+                            continue;
+                        }
+                    }
+                    for issue in issues {
+                        record(mode, &mut reports, line_number, issue)?;
+                    }
+                    let next_id = cards.last().map(|last| last.id + 1);
+                    for id in next_id.unwrap_or(card.id)..card.id {
+                        record(mode, &mut reports, line_number, Issue::MissingCard(id))?;
+                        cards.push(Scratchcard {
+                            id,
+                            winning: Vec::new(),
+                            have: Vec::new(),
+                        });
+                    }
+                    if let Some(first) = cards.first() {
+                        for (side, expected, got) in [
+                            (Side::Winning, first.winning.len(), card.winning.len()),
+                            (Side::Have, first.have.len(), card.have.len()),
+                        ] {
+                            if expected != got {
+                                let issue = Issue::SideLength {
+                                    side,
+                                    expected,
+                                    got,
+                                };
+                                record(mode, &mut reports, line_number, issue)?;
+                            }
+                        }
+                    }
+                    cards.push(card);
                 }
-
-                let score = matched.fold(0, |acc, _x| if acc == 0 { 1 } else { 2 * acc });
-                sum += score;
-            }
+                Err(issue) => record(mode, &mut reports, line_number, issue)?,
+            },
             Err(e) => eprintln!("Error reading line: {}", e),
         }
     }
 
-    Ok((sum, cards.iter().sum()))
+    Ok((cards, reports))
+}
+
+/// Number of copies owned of each card once every win has been cascaded.
+/// Wins pointing past the last card are dropped.
+fn copies(cards: &[Scratchcard]) -> Result<BTreeMap<u32, u64>, Error> {
+    let mut copies = BTreeMap::new();
+    for (idx, card) in cards.iter().enumerate() {
+        let expected = cards[0].id + idx as u32;
+        if card.id != expected {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Card ids must be contiguous, expected {expected}, got {}",
+                    card.id
+                ),
+            ));
+        }
+        copies.insert(card.id, 1);
+    }
+
+    let last_id = cards.last().map(|card| card.id).unwrap_or(0);
+    for card in cards {
+        let owned = copies[&card.id];
+        let won_until = last_id.min(card.id + card.matches() as u32);
+        for id in card.id + 1..=won_until {
+            *copies.get_mut(&id).unwrap() += owned;
+        }
+    }
+
+    Ok(copies)
+}
+
+fn process(input: String) -> Result<(u64, u64), Error> {
+    let (cards, _) = read_cards(input, ParseMode::Strict)?;
+
+    let sum = total_score(&cards)?;
+    let total = copies(&cards)?.values().sum();
+
+    Ok((sum, total))
 }
 
 fn main() -> Result<(), Error> {
     println!("Hello advent of code day 4 !");
 
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "copies") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day4/src/resources/input.txt".to_owned());
+        let (cards, _) = read_cards(input, ParseMode::Strict)?;
+        for (id, count) in copies(&cards)? {
+            println!("Card {id}: {count}");
+        }
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "lenient") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day4/src/resources/input.txt".to_owned());
+        let (cards, reports) = read_cards(input, ParseMode::Lenient)?;
+        for report in reports {
+            eprintln!("Line {}: {}", report.line, report.issue);
+        }
+        let result = total_score(&cards)?;
+        let total: u64 = copies(&cards)?.values().sum();
+        println!("The result is {} {}", result, total);
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "stream") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day4/src/resources/input.txt".to_owned());
+        let (result, cards) = process_streaming(read_input_file(input)?)?;
+        println!("The result is {} {}", result, cards);
+        return Ok(());
+    }
+
     let (result, cards) = process("day4/src/resources/input.txt".to_owned())?;
 
     println!("The result is {} {}", result, cards);
@@ -61,7 +318,10 @@ fn main() -> Result<(), Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::process;
+    use crate::{
+        copies, process, read_cards, total_score, Issue, ParseMode, Report, Scratchcard, Side,
+    };
+    use std::collections::BTreeMap;
 
     #[test]
     fn shoud_get_score() -> Result<(), String> {
@@ -86,4 +346,170 @@ mod tests {
         assert_eq!(cards, 30);
         Ok(())
     }
+
+    #[test]
+    fn shoud_get_copies_per_card() -> Result<(), String> {
+        let (cards, _) = read_cards("src/resources/test-input.txt".to_owned(), ParseMode::Strict)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let copies = copies(&cards).map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let counts: Vec<u64> = copies.values().copied().collect();
+        assert_eq!(counts, vec![1, 2, 4, 8, 14, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_clamp_copies_at_table_end() -> Result<(), String> {
+        let cards: Vec<Scratchcard> = ["Card 7: 1 2 3 | 1 2 3", "Card 8: 4 | 4"]
+            .iter()
+            .map(|line| Scratchcard::parse(line).map(|(card, _)| card))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let copies = copies(&cards).map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(copies[&7], 1);
+        assert_eq!(copies[&8], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_reject_non_contiguous_ids() {
+        let cards: Vec<Scratchcard> = ["Card 1: 1 | 1", "Card 3: 2 | 2"]
+            .iter()
+            .map(|line| Scratchcard::parse(line).unwrap().0)
+            .collect();
+        assert!(copies(&cards).is_err());
+        assert!(Scratchcard::parse("Crad 1: 1 | 1").is_err());
+    }
+
+    #[test]
+    fn shoud_reject_scores_beyond_64_bits() {
+        let numbers = |count: u32| {
+            (1..=count)
+                .map(|number| number.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let card = |id: u32, count: u32| {
+            let line = format!("Card {id}: {} | {}", numbers(count), numbers(count));
+            Scratchcard::parse(&line).unwrap().0
+        };
+        assert_eq!(card(1, 64).score().unwrap(), 1 << 63);
+        assert!(card(1, 70).score().is_err());
+        assert!(total_score(&[card(1, 64), card(2, 64)]).is_err());
+    }
+
+    #[test]
+    fn shoud_reject_invalid_cards_in_strict_mode() {
+        let result = read_cards(
+            "src/resources/test-input-invalid.txt".to_owned(),
+            ParseMode::Strict,
+        );
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 2: duplicate number 32 on the winning side"
+        );
+    }
+
+    #[test]
+    fn shoud_report_skipped_items_in_lenient_mode() -> Result<(), String> {
+        let (cards, reports) = read_cards(
+            "src/resources/test-input-invalid.txt".to_owned(),
+            ParseMode::Lenient,
+        )
+        .map_err(|e| format!("Test failed with error: {:?}", e))?;
+
+        let ids: Vec<u32> = cards.iter().map(|card| card.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert!(cards[3].winning.is_empty() && cards[3].have.is_empty());
+        assert_eq!(cards[1].winning, vec![13, 32, 20, 16]);
+        assert_eq!(
+            reports,
+            vec![
+                Report {
+                    line: 2,
+                    issue: Issue::DuplicateNumber {
+                        side: Side::Winning,
+                        number: 32
+                    }
+                },
+                Report {
+                    line: 2,
+                    issue: Issue::SideLength {
+                        side: Side::Winning,
+                        expected: 5,
+                        got: 4
+                    }
+                },
+                Report {
+                    line: 3,
+                    issue: Issue::InvalidNumber {
+                        side: Side::Have,
+                        token: "x1".to_owned()
+                    }
+                },
+                Report {
+                    line: 3,
+                    issue: Issue::SideLength {
+                        side: Side::Have,
+                        expected: 8,
+                        got: 7
+                    }
+                },
+                Report {
+                    line: 4,
+                    issue: Issue::MalformedCard("Card 4 41 92 73 84 69 | 59 84 76".to_owned())
+                },
+                Report {
+                    line: 5,
+                    issue: Issue::MissingCard(4)
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_count_copies_in_lenient_mode() -> Result<(), String> {
+        let (cards, _) = read_cards(
+            "src/resources/test-input-invalid.txt".to_owned(),
+            ParseMode::Lenient,
+        )
+        .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let copies = copies(&cards).map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let score = total_score(&cards).map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(score, 11);
+        assert_eq!(
+            copies,
+            BTreeMap::from([(1, 1), (2, 2), (3, 4), (4, 6), (5, 6)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_skip_out_of_order_cards_in_lenient_mode() -> Result<(), String> {
+        let input = "src/resources/test-input-order.txt";
+        let error = read_cards(input.to_owned(), ParseMode::Strict).unwrap_err();
+        assert_eq!(error.to_string(), "Line 3: card 2 comes after card 2");
+
+        let (cards, reports) = read_cards(input.to_owned(), ParseMode::Lenient)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let ids: Vec<u32> = cards.iter().map(|card| card.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(
+            reports,
+            vec![
+                Report {
+                    line: 3,
+                    issue: Issue::OutOfOrderCard { id: 2, previous: 2 }
+                },
+                Report {
+                    line: 4,
+                    issue: Issue::OutOfOrderCard { id: 1, previous: 2 }
+                },
+            ]
+        );
+        let copies = copies(&cards).map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(copies, BTreeMap::from([(1, 1), (2, 2), (3, 4)]));
+        Ok(())
+    }
 }
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, Error, ErrorKind};
+
+/// Yield the numbers of a space separated list without allocating, `None` for one that
+/// doesn't fit in a `u32`.
+fn numbers(bytes: &[u8]) -> impl Iterator<Item = Option<u32>> + '_ {
+    bytes
+        .split(|c| !c.is_ascii_digit())
+        .filter(|digits| !digits.is_empty())
+        .map(|digits| {
+            digits.iter().try_fold(0u32, |acc, digit| {
+                acc.checked_mul(10)?.checked_add((digit - b'0') as u32)
+            })
+        })
+}
+
+/// Winning numbers of a card, one bit per value below 128 and a sorted list for the rare
+/// larger ones, so memory doesn't grow with the values.
+struct WinningSet {
+    small: [u64; 2],
+    large: Vec<u32>,
+}
+
+impl WinningSet {
+    fn clear(&mut self) {
+        self.small = [0; 2];
+        self.large.clear();
+    }
+
+    fn insert(&mut self, value: u32) {
+        match self.small.get_mut(value as usize / 64) {
+            Some(word) => *word |= 1 << (value % 64),
+            None => self.large.push(value),
+        }
+    }
+
+    /// Sort the large values, to be called once every number has been inserted.
+    fn seal(&mut self) {
+        self.large.sort_unstable();
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        match self.small.get(value as usize / 64) {
+            Some(word) => word & (1 << (value % 64)) != 0,
+            None => self.large.binary_search(&value).is_ok(),
+        }
+    }
+}
+
+fn invalid(line: &[u8]) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "Invalid scratchcard '{}'",
+            String::from_utf8_lossy(line).trim_end()
+        ),
+    )
+}
+
+/// Score and total card count computed card by card.
+///
+/// Only the copies still owed to the next cards and the winning numbers of the current card
+/// are kept, so memory is bounded by the largest match count rather than by the number of
+/// cards.
+pub fn process_streaming(mut reader: impl BufRead) -> Result<(u64, u64), Error> {
+    let mut winning = WinningSet {
+        small: [0; 2],
+        large: Vec::new(),
+    };
+    let mut pending: VecDeque<u64> = VecDeque::new();
+    let mut previous_id: Option<u32> = None;
+    let (mut score, mut total) = (0u64, 0);
+
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            line.clear();
+            continue;
+        }
+
+        let colon = line.iter().position(|c| *c == b':');
+        let bar = line.iter().position(|c| *c == b'|');
+        let (Some(colon), Some(bar)) = (colon, bar) else {
+            return Err(invalid(&line));
+        };
+        if !line.starts_with(b"Card") || bar < colon {
+            return Err(invalid(&line));
+        }
+        let id = numbers(&line[..colon])
+            .next()
+            .flatten()
+            .ok_or_else(|| invalid(&line))?;
+        if let Some(previous) = previous_id {
+            if previous.checked_add(1) != Some(id) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Card ids must be contiguous, expected {}, got {id}",
+                        previous as u64 + 1
+                    ),
+                ));
+            }
+        }
+        previous_id = Some(id);
+
+        winning.clear();
+        for number in numbers(&line[colon + 1..bar]) {
+            winning.insert(number.ok_or_else(|| invalid(&line))?);
+        }
+        winning.seal();
+        let mut matches = 0;
+        for number in numbers(&line[bar + 1..]) {
+            if winning.contains(number.ok_or_else(|| invalid(&line))?) {
+                matches += 1;
+            }
+        }
+
+        if matches > 0 {
+            score = 1u64
+                .checked_shl(matches as u32 - 1)
+                .and_then(|points| score.checked_add(points))
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Card {id} has {matches} matches, the score doesn't fit in 64 bits"
+                        ),
+                    )
+                })?;
+        }
+
+        let owned = 1 + pending.pop_front().unwrap_or(0);
+        total += owned;
+        if pending.len() < matches {
+            pending.resize(matches, 0);
+        }
+        for copies in pending.iter_mut().take(matches) {
+            *copies += owned;
+        }
+
+        line.clear();
+    }
+
+    Ok((score, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::process_streaming;
+    use crate::process;
+    use std::io::Cursor;
+    use utils::read_input_file;
+
+    #[test]
+    fn shoud_match_table_cascade() -> Result<(), String> {
+        for input in ["src/resources/test-input.txt", "src/resources/input.txt"] {
+            let reader = read_input_file(input.to_owned())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            let streamed = process_streaming(reader)
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            let expected = process(input.to_owned())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            assert_eq!(streamed, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_stream_long_pile() {
+        // Every card wins the next one, so card n ends up with n copies
+        let pile: String = (1..=10_000)
+            .map(|id| format!("Card {id}: 1 2 | 2 3\n"))
+            .collect();
+        let (score, total) = process_streaming(Cursor::new(pile)).unwrap();
+        assert_eq!(score, 10_000);
+        assert_eq!(total, 10_000 * 10_001 / 2);
+    }
+
+    #[test]
+    fn shoud_reject_invalid_cards() {
+        assert!(process_streaming(Cursor::new("Card 1: 1 2 3\n")).is_err());
+        assert!(process_streaming(Cursor::new("Card 1: 1 | 1\nCard 3: 2 | 2\n")).is_err());
+        assert!(process_streaming(Cursor::new("Card 1: 99999999999999999999999 | 1\n")).is_err());
+        assert_eq!(
+            process_streaming(Cursor::new("Card 1: 4000000000 | 4000000000 1\n")).unwrap(),
+            (1, 1)
+        );
+        let numbers: Vec<String> = (1..=70).map(|number| number.to_string()).collect();
+        let card = format!("Card 1: {0} | {0}\n", numbers.join(" "));
+        assert!(process_streaming(Cursor::new(card)).is_err());
+    }
+}
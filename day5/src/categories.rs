@@ -0,0 +1,190 @@
+use crate::MapCollection;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io::{Error, ErrorKind};
+
+/// Split a `X-to-Y map:` header into its source and destination categories.
+pub fn parse_header(line: &str) -> Result<(String, String), Error> {
+    line.strip_suffix(" map:")
+        .and_then(|name| name.split_once("-to-"))
+        .filter(|(source, destination)| !source.is_empty() && !destination.is_empty())
+        .map(|(source, destination)| (source.to_owned(), destination.to_owned()))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid map header '{line}', expected 'X-to-Y map:'"),
+            )
+        })
+}
+
+pub fn categories(maps: &MapCollection) -> BTreeSet<&str> {
+    maps.keys()
+        .flat_map(|(source, destination)| [source.as_str(), destination.as_str()])
+        .collect()
+}
+
+fn links(maps: &MapCollection) -> HashMap<&str, Vec<&str>> {
+    let mut links: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (source, destination) in maps.keys() {
+        links.entry(source).or_default().push(destination);
+    }
+    links
+}
+
+/// Fail if following the maps can lead back to an already visited category.
+pub fn check_acyclic(maps: &MapCollection) -> Result<(), Error> {
+    fn visit<'a>(
+        category: &'a str,
+        links: &HashMap<&'a str, Vec<&'a str>>,
+        stack: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+    ) -> Result<(), Error> {
+        if let Some(idx) = stack.iter().position(|visited| *visited == category) {
+            let mut cycle = stack[idx..].to_vec();
+            cycle.push(category);
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Cyclic link: {}", cycle.join(" -> ")),
+            ));
+        }
+        if done.contains(category) {
+            return Ok(());
+        }
+
+        stack.push(category);
+        for next in links.get(category).into_iter().flatten() {
+            visit(next, links, stack, done)?;
+        }
+        stack.pop();
+        done.insert(category);
+        Ok(())
+    }
+
+    let links = links(maps);
+    let mut done = BTreeSet::new();
+    for category in categories(maps) {
+        visit(category, &links, &mut Vec::new(), &mut done)?;
+    }
+    Ok(())
+}
+
+/// Categories reachable by following the maps from the given one, itself included.
+pub fn reachable<'a>(maps: &'a MapCollection, from: &'a str) -> BTreeSet<&'a str> {
+    let links = links(maps);
+    let mut reached = BTreeSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(category) = queue.pop_front() {
+        for next in links.get(category).into_iter().flatten() {
+            if reached.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+/// Shortest chain of maps leading from one category to another.
+pub fn find_path(
+    maps: &MapCollection,
+    from: &str,
+    to: &str,
+) -> Result<Vec<(String, String)>, Error> {
+    let known = categories(maps);
+    for category in [from, to] {
+        if !known.contains(category) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Unknown category '{category}'"),
+            ));
+        }
+    }
+
+    let links = links(maps);
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(category) = queue.pop_front() {
+        if category == to {
+            let mut path = Vec::new();
+            let mut current = to;
+            while current != from {
+                let source = previous[current];
+                path.push((source.to_owned(), current.to_owned()));
+                current = source;
+            }
+            path.reverse();
+            return Ok(path);
+        }
+        for next in links.get(category).into_iter().flatten() {
+            if *next != from && !previous.contains_key(next) {
+                previous.insert(next, category);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!("No link from '{from}' to '{to}'"),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_acyclic, find_path, parse_header};
+    use crate::MapCollection;
+
+    fn collection(links: &[(&str, &str)]) -> MapCollection {
+        links
+            .iter()
+            .map(|(source, destination)| {
+                (
+                    (source.to_string(), destination.to_string()),
+                    vec![vec![0, 0, 1]],
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shoud_parse_headers() {
+        assert_eq!(
+            parse_header("seed-to-soil map:").unwrap(),
+            ("seed".to_owned(), "soil".to_owned())
+        );
+        assert!(parse_header("seed-soil map:").is_err());
+        assert!(parse_header("-to-soil map:").is_err());
+    }
+
+    #[test]
+    fn shoud_find_path_between_categories() {
+        let maps = collection(&[
+            ("seed", "soil"),
+            ("soil", "fertilizer"),
+            ("fertilizer", "water"),
+            ("soil", "mineral"),
+        ]);
+        let path = find_path(&maps, "seed", "water").unwrap();
+        let names: Vec<&str> = path
+            .iter()
+            .map(|(_, destination)| destination.as_str())
+            .collect();
+        assert_eq!(names, vec!["soil", "fertilizer", "water"]);
+        assert!(find_path(&maps, "seed", "seed").unwrap().is_empty());
+
+        let error = find_path(&maps, "water", "seed").unwrap_err();
+        assert_eq!(error.to_string(), "No link from 'water' to 'seed'");
+        let error = find_path(&maps, "seed", "light").unwrap_err();
+        assert_eq!(error.to_string(), "Unknown category 'light'");
+    }
+
+    #[test]
+    fn shoud_reject_cycles() {
+        assert!(check_acyclic(&collection(&[("seed", "soil"), ("soil", "water")])).is_ok());
+        let error = check_acyclic(&collection(&[
+            ("seed", "soil"),
+            ("soil", "water"),
+            ("water", "soil"),
+        ]))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Cyclic link: soil -> water -> soil");
+    }
+}
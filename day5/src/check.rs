@@ -0,0 +1,242 @@
+use crate::categories::{categories, reachable};
+use crate::piecewise::Piecewise;
+use crate::{normalize, Map, MapCollection, Range};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Something in an almanac that is valid but probably not intended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// Two lines of the same map share source numbers, the first one wins.
+    OverlappingSources { lines: (usize, usize), range: Range },
+    /// Two lines of the same map send numbers to the same destinations.
+    OverlappingDestinations { lines: (usize, usize), range: Range },
+    /// A line with a length of zero, which maps nothing.
+    ZeroLength { line: usize },
+    /// A line with a negative length, which maps nothing either.
+    NegativeLength { line: usize },
+    /// Once gaps are passed through unchanged, some values are reached several times or never.
+    NotBijective {
+        collisions: Vec<Range>,
+        holes: Vec<Range>,
+    },
+    /// A category that no chain of maps leads to from the seeds.
+    Unreached(String),
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::OverlappingSources { lines, range } => write!(
+                f,
+                "lines {} and {} overlap on sources [{}, {})",
+                lines.0, lines.1, range.0, range.1
+            ),
+            Finding::OverlappingDestinations { lines, range } => write!(
+                f,
+                "lines {} and {} overlap on destinations [{}, {})",
+                lines.0, lines.1, range.0, range.1
+            ),
+            Finding::ZeroLength { line } => write!(f, "line {line} has a zero length"),
+            Finding::NegativeLength { line } => write!(f, "line {line} has a negative length"),
+            Finding::NotBijective { collisions, holes } => write!(
+                f,
+                "not bijective, {} values reached several times and {} never reached",
+                collisions.iter().map(|r| r.1 - r.0).sum::<i64>(),
+                holes.iter().map(|r| r.1 - r.0).sum::<i64>()
+            ),
+            Finding::Unreached(category) => {
+                write!(f, "category '{category}' is never reached from 'seed'")
+            }
+        }
+    }
+}
+
+/// Finding located in the almanac, `link` is `None` for findings about the whole almanac.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub link: Option<(String, String)>,
+    pub finding: Finding,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.link {
+            Some((source, destination)) => {
+                write!(f, "{source}-to-{destination} map: {}", self.finding)
+            }
+            None => write!(f, "almanac: {}", self.finding),
+        }
+    }
+}
+
+/// Overlap of every pair of non empty ranges, lines are numbered from 1 within the map.
+fn overlaps(ranges: &[Range]) -> Vec<((usize, usize), Range)> {
+    let mut found = Vec::new();
+    for (i, first) in ranges.iter().enumerate() {
+        for (j, second) in ranges.iter().enumerate().skip(i + 1) {
+            let overlap = (first.0.max(second.0), first.1.min(second.1));
+            if overlap.0 < overlap.1 {
+                found.push(((i + 1, j + 1), overlap));
+            }
+        }
+    }
+    found
+}
+
+/// Values covered by several pieces and values covered by none, from 0 to the last covered one.
+fn coverage(map: &Map) -> (Vec<Range>, Vec<Range>) {
+    let (mut collisions, mut holes) = (Vec::new(), Vec::new());
+    let mut covered = 0;
+    for piece in Piecewise::from_map(map).inverse() {
+        if piece.start > covered {
+            holes.push((covered, piece.start));
+        } else if piece.start < covered {
+            collisions.push((piece.start, covered.min(piece.end)));
+        }
+        covered = covered.max(piece.end);
+    }
+    (normalize(collisions), normalize(holes))
+}
+
+pub fn check_map(map: &Map) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let sources: Vec<Range> = map
+        .iter()
+        .map(|line| (line[1], line[1] + line[2]))
+        .collect();
+    let destinations: Vec<Range> = map
+        .iter()
+        .map(|line| (line[0], line[0] + line[2]))
+        .collect();
+
+    for (idx, line) in map.iter().enumerate() {
+        match line[2].cmp(&0) {
+            Ordering::Equal => findings.push(Finding::ZeroLength { line: idx + 1 }),
+            Ordering::Less => findings.push(Finding::NegativeLength { line: idx + 1 }),
+            Ordering::Greater => {}
+        }
+    }
+    for (lines, range) in overlaps(&sources) {
+        findings.push(Finding::OverlappingSources { lines, range });
+    }
+    for (lines, range) in overlaps(&destinations) {
+        findings.push(Finding::OverlappingDestinations { lines, range });
+    }
+
+    let (collisions, holes) = coverage(map);
+    if !collisions.is_empty() || !holes.is_empty() {
+        findings.push(Finding::NotBijective { collisions, holes });
+    }
+    findings
+}
+
+/// Check every map of the almanac, then the categories reachable from the seeds.
+pub fn check_almanac(maps: &MapCollection) -> Vec<Report> {
+    let mut reports = Vec::new();
+    for (link, map) in maps {
+        reports.extend(check_map(map).into_iter().map(|finding| Report {
+            link: Some(link.clone()),
+            finding,
+        }));
+    }
+
+    let reached = reachable(maps, "seed");
+    for category in categories(maps) {
+        if !reached.contains(category) {
+            reports.push(Report {
+                link: None,
+                finding: Finding::Unreached(category.to_owned()),
+            });
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_almanac, check_map, Finding};
+    use crate::{parse_almanac, Map};
+
+    #[test]
+    fn shoud_accept_consistent_almanacs() -> Result<(), String> {
+        for input in ["src/resources/test-input.txt", "src/resources/input.txt"] {
+            let (_, maps) = parse_almanac(input.to_owned())
+                .map_err(|e| format!("Test failed with error: {:?}", e))?;
+            assert_eq!(check_almanac(&maps), vec![]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_report_inconsistent_maps() {
+        let map: Map = vec![
+            vec![50, 98, 2],
+            vec![51, 90, 10],
+            vec![0, 5, 0],
+            vec![0, 200, -3],
+        ];
+        assert_eq!(
+            check_map(&map),
+            vec![
+                Finding::ZeroLength { line: 3 },
+                Finding::NegativeLength { line: 4 },
+                Finding::OverlappingSources {
+                    lines: (1, 2),
+                    range: (98, 100)
+                },
+                Finding::OverlappingDestinations {
+                    lines: (1, 2),
+                    range: (51, 52)
+                },
+                // The gap below 90 already reaches 50..59, while 90..100 is left behind
+                Finding::NotBijective {
+                    collisions: vec![(50, 59)],
+                    holes: vec![(90, 100)],
+                },
+            ]
+        );
+        assert_eq!(check_map(&vec![vec![10, 0, 5], vec![0, 10, 5]]), vec![]);
+    }
+
+    #[test]
+    fn shoud_report_unreached_categories() -> Result<(), String> {
+        let (_, maps) = parse_almanac("src/resources/test-input-check.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let reports: Vec<String> = check_almanac(&maps)
+            .iter()
+            .map(|report| report.to_string())
+            .collect();
+        assert_eq!(
+            reports,
+            vec![
+                "seed-to-soil map: lines 1 and 2 overlap on sources [55, 60)",
+                "seed-to-soil map: not bijective, 5 values reached several times and 5 never reached",
+                "almanac: category 'light' is never reached from 'seed'",
+                "almanac: category 'location' is never reached from 'seed'",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_reject_malformed_map_lines() {
+        let path = std::env::temp_dir().join("day5-malformed-almanac.txt");
+        let path = path.to_string_lossy().into_owned();
+        let error = |almanac: &str| {
+            std::fs::write(&path, almanac).unwrap();
+            parse_almanac(path.clone()).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            error("seeds: 79 14\n\nseed-to-soil map:\n52 50 48\n50 98\n"),
+            "Line 5: expected '<destination> <source> <length>', got '50 98'"
+        );
+        assert_eq!(
+            error("seeds: 79 14\n\nseed-to-soil map:\n50 x 2\n"),
+            "Line 4: expected '<destination> <source> <length>', got '50 x 2'"
+        );
+        assert_eq!(error("seeds: 79 1a\n"), "Line 1: invalid seed '1a'");
+        std::fs::remove_file(path).unwrap();
+    }
+}
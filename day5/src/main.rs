@@ -1,24 +1,45 @@
-use std::collections::HashMap;
-use std::io::{BufRead, Error};
-use std::sync::{Arc, Mutex};
-use std::thread;
+mod categories;
+mod check;
+mod piecewise;
+mod svg;
+
+use categories::{check_acyclic, find_path, parse_header};
+use check::check_almanac;
+use piecewise::Piecewise;
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{BufRead, Error, ErrorKind};
+use std::str::FromStr;
 
 use utils::read_input_file;
 
 type Map = Vec<Vec<i64>>;
-type MapCollection = HashMap<String, Map>;
+/// Maps keyed by their `(source, destination)` categories.
+type MapCollection = BTreeMap<(String, String), Map>;
 
-fn parse_map(input: &str) -> Map {
+/// Parse the `<destination> <source> <length>` lines of a map, `first_line` being the line
+/// number of the first one in the almanac.
+fn parse_map(input: &str, first_line: usize) -> Result<Map, Error> {
     input
         .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|s| s.parse().unwrap())
-                .collect()
+        .enumerate()
+        .map(|(idx, line)| {
+            let numbers: Option<Vec<i64>> =
+                line.split_whitespace().map(|s| s.parse().ok()).collect();
+            numbers.filter(|numbers| numbers.len() == 3).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Line {}: expected '<destination> <source> <length>', got '{line}'",
+                        first_line + idx
+                    ),
+                )
+            })
         })
         .collect()
 }
 
+#[cfg(test)]
 fn convert_number(number: i64, map: &Map) -> i64 {
     for line in map {
         let dest_start = line[0];
@@ -33,141 +54,297 @@ fn convert_number(number: i64, map: &Map) -> i64 {
     number
 }
 
-fn find_lowest_location(seeds: Vec<i64>, maps: &MapCollection) -> i64 {
-    let seed_to_soil = maps.get("seed-to-soil").unwrap();
-    let soil_to_fertilizer = maps.get("soil-to-fertilizer").unwrap();
-    let fertilizer_to_water = maps.get("fertilizer-to-water").unwrap();
-    let water_to_light = maps.get("water-to-light").unwrap();
-    let light_to_temperature = maps.get("light-to-temperature").unwrap();
-    let temperature_to_humidity = maps.get("temperature-to-humidity").unwrap();
-    let humidity_to_location = maps.get("humidity-to-location").unwrap();
-
-    // Use Arc for shared access to maps
-    let shared_maps = Arc::new((
-        seed_to_soil.clone(),
-        soil_to_fertilizer.clone(),
-        fertilizer_to_water.clone(),
-        water_to_light.clone(),
-        light_to_temperature.clone(),
-        temperature_to_humidity.clone(),
-        humidity_to_location.clone(),
-    ));
-
-    // Use Mutex for synchronized access to the result vector
-    let result = Arc::new(Mutex::new(Vec::<i64>::new()));
-
-    // Iterate over seeds and spawn threads for parallel processing
-    let mut handles = vec![];
-
-    for seed in seeds {
-        let shared_maps = Arc::clone(&shared_maps);
-        let result = Arc::clone(&result);
-
-        // Spawn a thread for each seed
-        let handle = thread::spawn(move || {
-            let (
-                seed_to_soil,
-                soil_to_fertilizer,
-                fertilizer_to_water,
-                water_to_light,
-                light_to_temperature,
-                temperature_to_humidity,
-                humidity_to_location,
-            ) = &*shared_maps;
-
-            let soil = convert_number(seed, seed_to_soil);
-            let fertilizer = convert_number(soil, soil_to_fertilizer);
-            let water = convert_number(fertilizer, fertilizer_to_water);
-            let light = convert_number(water, water_to_light);
-            let temperature = convert_number(light, light_to_temperature);
-            let humidity = convert_number(temperature, temperature_to_humidity);
-            let location = convert_number(humidity, humidity_to_location);
-
-            // Use Mutex to safely update the result vector
-            let mut result = result.lock().unwrap();
-            result.push(location);
-        });
+/// Half-open range of numbers `[start, end)`.
+type Range = (i64, i64);
+
+/// Sort the ranges, drop the empty ones and merge the overlapping or touching ones.
+fn normalize(mut ranges: Vec<Range>) -> Vec<Range> {
+    ranges.retain(|(start, end)| start < end);
+    ranges.sort_unstable();
+    let mut merged: Vec<Range> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Push whole ranges through a map, splitting them at the map line boundaries.
+/// Like `convert_number`, the first matching line wins and gaps map to themselves.
+fn convert_ranges(ranges: &[Range], map: &Map) -> Vec<Range> {
+    let mut pending = ranges.to_vec();
+    let mut converted = Vec::new();
+
+    for line in map {
+        let source_start = line[1];
+        let source_end = line[1] + line[2];
+        let offset = line[0] - line[1];
+
+        let mut remaining = Vec::new();
+        for (start, end) in pending {
+            let overlap = (start.max(source_start), end.min(source_end));
+            if overlap.0 >= overlap.1 {
+                remaining.push((start, end));
+                continue;
+            }
 
-        handles.push(handle);
+            converted.push((overlap.0 + offset, overlap.1 + offset));
+            if start < overlap.0 {
+                remaining.push((start, overlap.0));
+            }
+            if overlap.1 < end {
+                remaining.push((overlap.1, end));
+            }
+        }
+        pending = remaining;
     }
 
-    // Wait for all threads to finish
-    for handle in handles {
-        handle.join().unwrap();
+    converted.extend(pending);
+    normalize(converted)
+}
+
+/// Push the ranges through every map leading from one category to another.
+fn convert_between(
+    ranges: Vec<Range>,
+    maps: &MapCollection,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Range>, Error> {
+    let mut ranges = normalize(ranges);
+    for link in find_path(maps, from, to)? {
+        ranges = convert_ranges(&ranges, &maps[&link]);
     }
+    Ok(ranges)
+}
 
-    // Extract the result vector from the Mutex
-    let result = Arc::try_unwrap(result).unwrap().into_inner().unwrap();
+fn find_lowest_location(seeds: Vec<Range>, maps: &MapCollection) -> Result<i64, Error> {
+    let locations = convert_between(seeds, maps, "seed", "location")?;
 
-    // Find the minimum location from the result vector
-    *result.iter().min().unwrap()
+    // Ranges are sorted, the lowest location is the start of the first one
+    locations
+        .first()
+        .map(|range| range.0)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No seeds in the almanac"))
 }
 
-fn generate_ranges(seeds: Vec<i64>) -> Vec<i64> {
-    let mut result = Vec::new();
+/// How the numbers of the `seeds:` line are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeedMode {
+    /// Every number is a seed on its own.
+    Individual,
+    /// Numbers come in `start length` pairs.
+    Ranges,
+}
 
-    // Iterate over pairs in the input vector
-    let mut iter = seeds.iter();
-    while let Some(&start) = iter.next() {
-        if let Some(&length) = iter.next() {
-            // Generate the range and append to the result vector
-            let end = start + length;
-            result.extend(start..end);
-        } else {
-            // Handle the case where there is an odd number of elements in the input vector
-            panic!("Odd number of elements in the seeds vector");
+impl FromStr for SeedMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "individual" => Ok(SeedMode::Individual),
+            "ranges" => Ok(SeedMode::Ranges),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid seed mode '{s}', expected 'individual' or 'ranges'"),
+            )),
         }
     }
+}
 
-    result
+fn generate_ranges(seeds: Vec<i64>, mode: SeedMode) -> Result<Vec<Range>, Error> {
+    match mode {
+        SeedMode::Individual => Ok(seeds.into_iter().map(|seed| (seed, seed + 1)).collect()),
+        SeedMode::Ranges => {
+            if !seeds.len().is_multiple_of(2) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Odd number of seeds ({}), ranges need start and length pairs",
+                        seeds.len()
+                    ),
+                ));
+            }
+            Ok(seeds
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[0] + pair[1]))
+                .collect())
+        }
+    }
 }
 
-fn process(input: String) -> Result<i64, Error> {
+fn parse_almanac(input: String) -> Result<(Vec<i64>, MapCollection), Error> {
     let reader = read_input_file(input)?;
 
-    let mut maps: MapCollection = HashMap::new();
+    let mut maps: MapCollection = BTreeMap::new();
     let mut seeds: Vec<i64> = Vec::new();
-    let mut current_map_name = String::new();
+    let mut current_link: Option<(String, String)> = None;
     let mut current_map_lines: Vec<String> = Vec::new();
+    let mut current_first_line = 0;
+
+    let mut flush = |link: &Option<(String, String)>, lines: &mut Vec<String>, first_line| {
+        if let (Some(link), false) = (link, lines.is_empty()) {
+            let map = parse_map(&lines.join("\n"), first_line)?;
+            if maps.insert(link.clone(), map).is_some() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Duplicate map '{}-to-{}'", link.0, link.1),
+                ));
+            }
+            lines.clear();
+        }
+        Ok(())
+    };
 
-    for line in reader.lines() {
+    for (idx, line) in reader.lines().enumerate() {
         let line = line?;
         if line.is_empty() {
-            if !current_map_name.is_empty() && !current_map_lines.is_empty() {
-                let map = parse_map(&current_map_lines.join("\n"));
-                maps.insert(current_map_name.clone(), map);
-                current_map_lines.clear();
-            }
+            flush(&current_link, &mut current_map_lines, current_first_line)?;
         } else if line.contains("seeds:") {
             seeds = line
                 .trim_start_matches("seeds:")
                 .split_whitespace()
-                .map(|s| s.parse().unwrap())
-                .collect();
+                .map(|s| {
+                    s.parse().map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Line {}: invalid seed '{s}'", idx + 1),
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?;
         } else if line.ends_with(" map:") {
-            current_map_name = line.trim_end_matches(" map:").to_string();
+            flush(&current_link, &mut current_map_lines, current_first_line)?;
+            current_link = Some(parse_header(&line)?);
+        } else if current_link.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Map line '{line}' found before any map header"),
+            ));
         } else {
+            if current_map_lines.is_empty() {
+                current_first_line = idx + 1;
+            }
             current_map_lines.push(line);
         }
     }
+    flush(&current_link, &mut current_map_lines, current_first_line)?;
 
-    if !current_map_name.is_empty() && !current_map_lines.is_empty() {
-        let map = parse_map(&current_map_lines.join("\n"));
-        maps.insert(current_map_name.clone(), map);
-    }
+    check_acyclic(&maps)?;
+    Ok((seeds, maps))
+}
 
-    println!("Generate seeds...");
-    let updated_seeds = generate_ranges(seeds);
-    println!("Find lowest location");
-    let lowest_location = find_lowest_location(updated_seeds, &maps);
+fn process(input: String, mode: SeedMode) -> Result<i64, Error> {
+    let (seeds, maps) = parse_almanac(input)?;
 
-    Ok(lowest_location)
+    let seed_ranges = generate_ranges(seeds, mode)?;
+    find_lowest_location(seed_ranges, &maps)
 }
 
 fn main() -> Result<(), Error> {
     println!("Hello advent of code day 5 !");
 
-    let res = process("day5/src/resources/input.txt".to_owned())?;
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "query") {
+        let [_, _, input, from, to, values @ ..] = args.as_slice() else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Usage: query <input> <from> <to> <value>...",
+            ));
+        };
+        let (_, maps) = parse_almanac(input.to_owned())?;
+        let composed = Piecewise::from_path(&maps, from, to)?;
+        for value in values {
+            let value: i64 = value.parse().map_err(|_| {
+                Error::new(ErrorKind::InvalidInput, format!("Invalid value '{value}'"))
+            })?;
+            println!("{from} {value} -> {to} {}", composed.apply(value));
+        }
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "check") {
+        let input = args
+            .get(2)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Usage: check <input>"))?;
+        let (_, maps) = parse_almanac(input.to_owned())?;
+        let reports = check_almanac(&maps);
+        for report in &reports {
+            println!("{input}: {report}");
+        }
+        if !reports.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} issues found in {input}", reports.len()),
+            ));
+        }
+        println!("{input}: ok");
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "svg") {
+        let input = args
+            .get(2)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Usage: svg <input> [<output>]"))?;
+        let output = args.get(3).cloned().unwrap_or_else(|| {
+            let path = std::env::temp_dir().join("day5-almanac.svg");
+            path.to_string_lossy().into_owned()
+        });
+        let (seeds, maps) = parse_almanac(input.to_owned())?;
+        svg::write_svg(&output, &maps, &generate_ranges(seeds, SeedMode::Ranges)?)?;
+        println!("Almanac written to {output}");
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "compose") {
+        let input = args.get(2).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Usage: compose <input> [<from> <to>]",
+            )
+        })?;
+        let from = args.get(3).map_or("seed", String::as_str);
+        let to = args.get(4).map_or("location", String::as_str);
+        let (seeds, maps) = parse_almanac(input.to_owned())?;
+        let composed = Piecewise::from_path(&maps, from, to)?;
+        print!("{}", composed.to_map(from, to));
+        println!("{} pieces", composed.pieces().len());
+        if let Some(lowest) = composed.lowest(&generate_ranges(seeds, SeedMode::Ranges)?) {
+            println!("Lowest {to} from the {from} ranges: {lowest}");
+        }
+        return Ok(());
+    }
+    if args.get(1).is_some_and(|arg| arg == "preimage") {
+        let [_, _, input, locations @ ..] = args.as_slice() else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Usage: preimage <input> <location>...",
+            ));
+        };
+        let (_, maps) = parse_almanac(input.to_owned())?;
+        let composed = Piecewise::from_path(&maps, "seed", "location")?;
+        for location in locations {
+            let location: i64 = location.parse().map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid location '{location}'"),
+                )
+            })?;
+            println!(
+                "location {location} <- seeds {:?}",
+                composed.preimage((location, location + 1))
+            );
+        }
+        return Ok(());
+    }
+
+    let mode = match args.get(1) {
+        Some(mode) => mode.parse()?,
+        None => SeedMode::Ranges,
+    };
+    let input = args
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| "day5/src/resources/input.txt".to_owned());
+    let res = process(input, mode)?;
     println!("The result is {}", res);
 
     Ok(())
@@ -175,13 +352,112 @@ fn main() -> Result<(), Error> {
 
 #[cfg(test)]
 mod test {
-    use crate::process;
+    use crate::{
+        convert_between, convert_number, convert_ranges, generate_ranges, normalize, parse_almanac,
+        process, Map, SeedMode,
+    };
 
     #[test]
     fn shoud_get_lowest_location() -> Result<(), String> {
-        let result = process("src/resources/test-input.txt".to_owned())
+        let result = process("src/resources/test-input.txt".to_owned(), SeedMode::Ranges)
             .map_err(|e| format!("Test failed with error: {:?}", e))?;
         assert_eq!(result, 46);
         Ok(())
     }
+
+    #[test]
+    fn shoud_ignore_empty_seed_ranges() -> Result<(), String> {
+        let result = process(
+            "src/resources/test-input-empty-range.txt".to_owned(),
+            SeedMode::Ranges,
+        )
+        .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 46);
+        assert_eq!(normalize(vec![(5, 5), (79, 93), (10, 4)]), vec![(79, 93)]);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_get_lowest_location_of_individual_seeds() -> Result<(), String> {
+        let result = process(
+            "src/resources/test-input.txt".to_owned(),
+            SeedMode::Individual,
+        )
+        .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 35);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_get_lowest_location_real_input() -> Result<(), String> {
+        let result = process("src/resources/input.txt".to_owned(), SeedMode::Ranges)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 104070862);
+        let result = process("src/resources/input.txt".to_owned(), SeedMode::Individual)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 324724204);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_reject_odd_seed_ranges() {
+        assert_eq!(
+            generate_ranges(vec![79, 14, 55], SeedMode::Individual).unwrap(),
+            vec![(79, 80), (14, 15), (55, 56)]
+        );
+        let error = generate_ranges(vec![79, 14, 55], SeedMode::Ranges).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Odd number of seeds (3), ranges need start and length pairs"
+        );
+        assert!("pairs".parse::<SeedMode>().is_err());
+    }
+
+    #[test]
+    fn shoud_split_ranges_at_map_boundaries() {
+        let map: Map = vec![vec![50, 98, 2], vec![52, 50, 48]];
+        let converted = convert_ranges(&[(45, 100)], &map);
+        // [45, 50) is a gap, [50, 98) and [98, 100) are shifted next to it
+        assert_eq!(converted, vec![(45, 100)]);
+        let converted = convert_ranges(&[(40, 60), (97, 99)], &map);
+        assert_eq!(converted, vec![(40, 51), (52, 62), (99, 100)]);
+
+        for number in 40..110 {
+            let converted = convert_ranges(&[(number, number + 1)], &map);
+            assert_eq!(
+                converted,
+                vec![(
+                    convert_number(number, &map),
+                    convert_number(number, &map) + 1
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn shoud_convert_between_any_categories() -> Result<(), String> {
+        let (_, maps) = parse_almanac("src/resources/test-input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        // Seed 79 goes through soil 81, fertilizer 81, water 81, light 74 and humidity 78
+        let humidity = convert_between(vec![(81, 82)], &maps, "soil", "humidity")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(humidity, vec![(78, 79)]);
+        let fertilizer = convert_between(vec![(79, 80)], &maps, "seed", "fertilizer")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(fertilizer, vec![(81, 82)]);
+        assert!(convert_between(vec![(79, 80)], &maps, "location", "seed").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_reject_invalid_almanacs() {
+        let error = parse_almanac("src/resources/test-input-cycle.txt".to_owned()).unwrap_err();
+        assert_eq!(error.to_string(), "Cyclic link: soil -> water -> soil");
+        let error = process(
+            "src/resources/test-input-missing.txt".to_owned(),
+            SeedMode::Ranges,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "No link from 'seed' to 'location'");
+    }
 }
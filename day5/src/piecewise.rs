@@ -0,0 +1,286 @@
+use crate::categories::find_path;
+use crate::{normalize, Map, MapCollection, Range};
+use std::io::Error;
+
+/// Upper bound of the numbers handled by the almanac, leaving room for the offsets.
+const DOMAIN_END: i64 = i64::MAX / 4;
+
+/// Numbers of `[start, end)` are shifted by `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Piece {
+    pub start: i64,
+    pub end: i64,
+    pub offset: i64,
+}
+
+/// Piecewise-linear function over `[0, DOMAIN_END)`, as sorted contiguous pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piecewise {
+    pieces: Vec<Piece>,
+}
+
+impl Piecewise {
+    fn identity() -> Self {
+        Piecewise {
+            pieces: vec![Piece {
+                start: 0,
+                end: DOMAIN_END,
+                offset: 0,
+            }],
+        }
+    }
+
+    /// Sort the pieces and merge the neighbours sharing the same offset.
+    fn from_pieces(mut pieces: Vec<Piece>) -> Self {
+        pieces.sort_unstable_by_key(|piece| piece.start);
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces.into_iter().filter(|piece| piece.start < piece.end) {
+            match merged.last_mut() {
+                Some(last) if last.end == piece.start && last.offset == piece.offset => {
+                    last.end = piece.end
+                }
+                _ => merged.push(piece),
+            }
+        }
+        Piecewise { pieces: merged }
+    }
+
+    /// Same semantics as `convert_number`: the first matching line wins, gaps are unchanged.
+    pub fn from_map(map: &Map) -> Self {
+        let mut unclaimed = vec![(0, DOMAIN_END)];
+        let mut pieces = Vec::new();
+
+        for line in map {
+            let (source_start, source_end) = (line[1], line[1] + line[2]);
+            let offset = line[0] - line[1];
+
+            let mut remaining = Vec::new();
+            for (start, end) in unclaimed {
+                let overlap = (start.max(source_start), end.min(source_end));
+                if overlap.0 >= overlap.1 {
+                    remaining.push((start, end));
+                    continue;
+                }
+                pieces.push(Piece {
+                    start: overlap.0,
+                    end: overlap.1,
+                    offset,
+                });
+                if start < overlap.0 {
+                    remaining.push((start, overlap.0));
+                }
+                if overlap.1 < end {
+                    remaining.push((overlap.1, end));
+                }
+            }
+            unclaimed = remaining;
+        }
+
+        pieces.extend(unclaimed.into_iter().map(|(start, end)| Piece {
+            start,
+            end,
+            offset: 0,
+        }));
+        Piecewise::from_pieces(pieces)
+    }
+
+    /// Collapse every map between two categories into a single function.
+    pub fn from_path(maps: &MapCollection, from: &str, to: &str) -> Result<Self, Error> {
+        let mut composed = Piecewise::identity();
+        for link in find_path(maps, from, to)? {
+            composed = composed.then(&Piecewise::from_map(&maps[&link]));
+        }
+        Ok(composed)
+    }
+
+    pub fn pieces(&self) -> &[Piece] {
+        &self.pieces
+    }
+
+    /// Index of the piece holding the value, if it is inside the domain.
+    fn piece_of(&self, value: i64) -> Option<usize> {
+        let idx = self.pieces.partition_point(|piece| piece.end <= value);
+        self.pieces
+            .get(idx)
+            .filter(|piece| piece.start <= value)
+            .map(|_| idx)
+    }
+
+    pub fn apply(&self, value: i64) -> i64 {
+        match self.piece_of(value) {
+            Some(idx) => value + self.pieces[idx].offset,
+            None => value,
+        }
+    }
+
+    /// `next(self(x))`, splitting each piece where its image crosses a piece of `next`.
+    pub fn then(&self, next: &Piecewise) -> Piecewise {
+        let mut pieces = Vec::new();
+        for piece in &self.pieces {
+            let mut start = piece.start;
+            while start < piece.end {
+                let image = start + piece.offset;
+                let (end, offset) = match next.piece_of(image) {
+                    Some(idx) => {
+                        let next_piece = next.pieces[idx];
+                        (
+                            piece.end.min(next_piece.end - piece.offset),
+                            piece.offset + next_piece.offset,
+                        )
+                    }
+                    // Outside of the next domain the values are left unchanged
+                    None => {
+                        let following = next.pieces.iter().find(|p| p.start > image);
+                        let end = following.map_or(piece.end, |p| p.start - piece.offset);
+                        (piece.end.min(end), piece.offset)
+                    }
+                };
+                pieces.push(Piece { start, end, offset });
+                start = end;
+            }
+        }
+        Piecewise::from_pieces(pieces)
+    }
+
+    /// Ranges reached from the given ones.
+    pub fn image(&self, ranges: &[Range]) -> Vec<Range> {
+        let mut image = Vec::new();
+        for (start, end) in normalize(ranges.to_vec()) {
+            for piece in &self.pieces {
+                let overlap = (start.max(piece.start), end.min(piece.end));
+                if overlap.0 < overlap.1 {
+                    image.push((overlap.0 + piece.offset, overlap.1 + piece.offset));
+                }
+            }
+        }
+        normalize(image)
+    }
+
+    /// Lowest value reached from the given ranges.
+    pub fn lowest(&self, ranges: &[Range]) -> Option<i64> {
+        self.image(ranges).first().map(|range| range.0)
+    }
+
+    /// Inverse relation: the pieces sorted by the range they map to, with the offset reversed.
+    /// Several pieces can reach the same values when the destinations overlap.
+    pub fn inverse(&self) -> Vec<Piece> {
+        let mut inverse: Vec<Piece> = self
+            .pieces
+            .iter()
+            .map(|piece| Piece {
+                start: piece.start + piece.offset,
+                end: piece.end + piece.offset,
+                offset: -piece.offset,
+            })
+            .collect();
+        inverse.sort_unstable_by_key(|piece| (piece.start, piece.end));
+        inverse
+    }
+
+    /// Every source range mapping into the given range of values.
+    pub fn preimage(&self, range: Range) -> Vec<Range> {
+        let inverse = self.inverse();
+        let mut sources = Vec::new();
+        for piece in inverse.iter().take_while(|piece| piece.start < range.1) {
+            let overlap = (range.0.max(piece.start), range.1.min(piece.end));
+            if overlap.0 < overlap.1 {
+                sources.push((overlap.0 + piece.offset, overlap.1 + piece.offset));
+            }
+        }
+        normalize(sources)
+    }
+
+    /// Export the function as an almanac map, identity pieces being left out as gaps.
+    pub fn to_map(&self, from: &str, to: &str) -> String {
+        let mut table = format!("{from}-to-{to} map:\n");
+        for piece in self.pieces.iter().filter(|piece| piece.offset != 0) {
+            table.push_str(&format!(
+                "{} {} {}\n",
+                piece.start + piece.offset,
+                piece.start,
+                piece.end - piece.start
+            ));
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Piecewise;
+    use crate::categories::find_path;
+    use crate::{convert_number, generate_ranges, parse_almanac, MapCollection, SeedMode};
+
+    fn chained(value: i64, maps: &MapCollection) -> i64 {
+        find_path(maps, "seed", "location")
+            .unwrap()
+            .iter()
+            .fold(value, |value, link| convert_number(value, &maps[link]))
+    }
+
+    #[test]
+    fn shoud_compose_the_whole_almanac() -> Result<(), String> {
+        let (seeds, maps) = parse_almanac("src/resources/test-input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let composed = Piecewise::from_path(&maps, "seed", "location")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+
+        for seed in 0..150 {
+            assert_eq!(composed.apply(seed), chained(seed, &maps), "seed {seed}");
+        }
+        assert_eq!(
+            composed.lowest(&generate_ranges(seeds, SeedMode::Ranges).unwrap()),
+            Some(46)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_invert_the_whole_almanac() -> Result<(), String> {
+        let (_, maps) = parse_almanac("src/resources/input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let composed = Piecewise::from_path(&maps, "seed", "location")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+
+        for piece in composed.pieces().iter().take(40) {
+            for seed in [piece.start, piece.end - 1, (piece.start + piece.end) / 2] {
+                let location = chained(seed, &maps);
+                assert_eq!(composed.apply(seed), location);
+
+                let sources = composed.preimage((location, location + 1));
+                assert!(sources
+                    .iter()
+                    .any(|(start, end)| *start <= seed && seed < *end));
+                for (start, end) in sources {
+                    assert_eq!(chained(start, &maps), location);
+                    assert_eq!(end, start + 1);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_export_composed_table() -> Result<(), String> {
+        let (_, maps) = parse_almanac("src/resources/test-input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let composed = Piecewise::from_path(&maps, "seed", "location")
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let table = composed.to_map("seed", "location");
+        assert!(table.starts_with("seed-to-location map:\n"));
+
+        let map = crate::parse_map(
+            table
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .as_str(),
+            2,
+        )
+        .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let reloaded = Piecewise::from_map(&map);
+        assert_eq!(reloaded, composed);
+        Ok(())
+    }
+}
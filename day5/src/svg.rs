@@ -0,0 +1,204 @@
+use crate::categories::find_path;
+use crate::piecewise::Piecewise;
+use crate::{normalize, MapCollection, Range};
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+const COLUMN_GAP: f64 = 220.0;
+const MARGIN: f64 = 40.0;
+const HEIGHT: f64 = 600.0;
+const COLUMN_WIDTH: f64 = 8.0;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Vertical position of a value, every column sharing the same scale.
+struct Scale(f64);
+
+impl Scale {
+    fn y(&self, value: i64) -> f64 {
+        MARGIN + value as f64 / self.0 * HEIGHT
+    }
+}
+
+fn column_x(column: usize) -> f64 {
+    MARGIN + column as f64 * COLUMN_GAP
+}
+
+/// Band joining `source` on the left column to `destination` on the right one.
+fn band(column: usize, source: Range, destination: Range, scale: &Scale, style: &str) -> String {
+    let (left, right) = (column_x(column) + COLUMN_WIDTH, column_x(column + 1));
+    format!(
+        "<polygon points=\"{left:.1},{:.1} {right:.1},{:.1} {right:.1},{:.1} {left:.1},{:.1}\" {style}/>\n",
+        scale.y(source.0),
+        scale.y(destination.0),
+        scale.y(destination.1),
+        scale.y(source.1)
+    )
+}
+
+/// Pieces of the ranges with the range each one is sent to by the layer.
+fn split(ranges: &[Range], layer: &Piecewise) -> Vec<(Range, Range)> {
+    let mut parts = Vec::new();
+    for &(start, end) in ranges {
+        for piece in layer.pieces() {
+            let overlap = (start.max(piece.start), end.min(piece.end));
+            if overlap.0 < overlap.1 {
+                parts.push((
+                    overlap,
+                    (overlap.0 + piece.offset, overlap.1 + piece.offset),
+                ));
+            }
+        }
+    }
+    parts
+}
+
+/// SVG of the maps leading from seeds to locations.
+///
+/// Each category is a column, the shifted blocks of every map are coloured bands between
+/// two columns, the seed ranges are traced in grey and the path of the lowest location in red.
+pub fn render_svg(maps: &MapCollection, seeds: &[Range]) -> Result<String, Error> {
+    let path = find_path(maps, "seed", "location")?;
+    let layers: Vec<Piecewise> = path
+        .iter()
+        .map(|link| Piecewise::from_map(&maps[link]))
+        .collect();
+    let mut categories = vec!["seed"];
+    categories.extend(path.iter().map(|(_, destination)| destination.as_str()));
+
+    // Ranges reached at every column, starting with the seeds
+    let mut traced = vec![normalize(seeds.to_vec())];
+    for layer in &layers {
+        let next = layer.image(traced.last().unwrap());
+        traced.push(next);
+    }
+
+    let highest = path
+        .iter()
+        .flat_map(|link| &maps[link])
+        .flat_map(|line| [line[0] + line[2], line[1] + line[2]])
+        .chain(traced.iter().flatten().map(|range| range.1))
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let scale = Scale(highest as f64);
+
+    let mut body = String::new();
+    for (column, link) in path.iter().enumerate() {
+        let count = maps[link].len().max(1);
+        for (idx, line) in maps[link].iter().enumerate() {
+            let hue = idx * 360 / count;
+            let style = format!("class=\"block\" fill=\"hsl({hue}, 70%, 55%)\"");
+            let source = (line[1], line[1] + line[2]);
+            let destination = (line[0], line[0] + line[2]);
+            body.push_str(&band(column, source, destination, &scale, &style));
+        }
+    }
+
+    for (column, layer) in layers.iter().enumerate() {
+        for (source, destination) in split(&traced[column], layer) {
+            body.push_str(&band(
+                column,
+                source,
+                destination,
+                &scale,
+                "class=\"trace\" fill=\"#444\" fill-opacity=\"0.6\"",
+            ));
+        }
+    }
+
+    for (column, category) in categories.iter().enumerate() {
+        let x = column_x(column);
+        body.push_str(&format!(
+            "<rect class=\"column\" x=\"{x:.1}\" y=\"{MARGIN:.1}\" width=\"{COLUMN_WIDTH:.1}\" height=\"{HEIGHT:.1}\" fill=\"#ccc\"/>\n"
+        ));
+        for (start, end) in &traced[column] {
+            body.push_str(&format!(
+                "<rect class=\"range\" x=\"{x:.1}\" y=\"{:.1}\" width=\"{COLUMN_WIDTH:.1}\" height=\"{:.1}\" fill=\"#222\"/>\n",
+                scale.y(*start),
+                (scale.y(*end) - scale.y(*start)).max(1.0)
+            ));
+        }
+        body.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n",
+            x + COLUMN_WIDTH / 2.0,
+            MARGIN / 2.0,
+            escape(category)
+        ));
+    }
+
+    // Follow back the lowest location to one of its seeds, then forward through every layer
+    let lowest = traced
+        .last()
+        .and_then(|ranges| ranges.first())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No seeds in the almanac"))?;
+    let composed = Piecewise::from_path(maps, "seed", "location")?;
+    let seed = composed
+        .preimage((lowest.0, lowest.0 + 1))
+        .iter()
+        .map(|source| source.0)
+        .find(|seed| {
+            traced[0]
+                .iter()
+                .any(|seeds| seeds.0 <= *seed && *seed < seeds.1)
+        })
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Lowest location without a seed"))?;
+    let mut value = seed;
+    let mut points = vec![format!(
+        "{:.1},{:.1}",
+        column_x(0) + COLUMN_WIDTH / 2.0,
+        scale.y(value)
+    )];
+    for (column, layer) in layers.iter().enumerate() {
+        value = layer.apply(value);
+        points.push(format!(
+            "{:.1},{:.1}",
+            column_x(column + 1) + COLUMN_WIDTH / 2.0,
+            scale.y(value)
+        ));
+    }
+    body.push_str(&format!(
+        "<polyline class=\"lowest\" points=\"{}\" fill=\"none\" stroke=\"#e00\" stroke-width=\"2\"><title>seed {seed} -> location {value}</title></polyline>\n",
+        points.join(" ")
+    ));
+
+    let width = column_x(categories.len() - 1) + COLUMN_WIDTH + MARGIN;
+    let height = HEIGHT + 2.0 * MARGIN;
+    Ok(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" font-family=\"monospace\" font-size=\"12\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{body}</svg>\n"
+    ))
+}
+
+pub fn write_svg(path: &str, maps: &MapCollection, seeds: &[Range]) -> Result<(), Error> {
+    fs::write(path, render_svg(maps, seeds)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_svg;
+    use crate::{generate_ranges, parse_almanac, SeedMode};
+
+    #[test]
+    fn shoud_render_every_layer() -> Result<(), String> {
+        let (seeds, maps) = parse_almanac("src/resources/test-input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let seeds = generate_ranges(seeds, SeedMode::Ranges)
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let svg =
+            render_svg(&maps, &seeds).map_err(|e| format!("Test failed with error: {:?}", e))?;
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("class=\"column\"").count(), 8);
+        let lines: usize = maps.values().map(|map| map.len()).sum();
+        assert_eq!(svg.matches("class=\"block\"").count(), lines);
+        assert!(svg.contains(">humidity</text>"));
+        assert!(svg.contains("<title>seed 82 -> location 46</title>"));
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+/// How the boat turns the time spent holding the button into distance.
+///
+/// Every ms held adds `acceleration` mm/ms of speed up to `max_speed`, then the boat waits
+/// `startup` ms before moving for the rest of the race.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boat {
+    pub acceleration: u128,
+    pub max_speed: Option<u128>,
+    pub startup: u128,
+}
+
+/// The puzzle rules: one mm/ms per ms held, no cap and no startup penalty.
+impl Default for Boat {
+    fn default() -> Self {
+        Boat {
+            acceleration: 1,
+            max_speed: None,
+            startup: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaceReport {
+    pub race_time: u128,
+    pub record: u128,
+    /// Inclusive range of hold times beating the record.
+    pub winning: Option<(u128, u128)>,
+    /// Shortest hold reaching the longest distance.
+    pub optimal_hold: u128,
+    pub best_distance: u128,
+}
+
+impl RaceReport {
+    pub fn ways_to_win(&self) -> u128 {
+        self.winning
+            .map_or(0, |(lowest, highest)| highest - lowest + 1)
+    }
+
+    /// How far past the record the optimal hold goes.
+    pub fn margin(&self) -> Option<u128> {
+        self.best_distance
+            .checked_sub(self.record)
+            .filter(|m| *m > 0)
+    }
+}
+
+impl fmt::Display for RaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "race {} ms, record {} mm: ", self.race_time, self.record)?;
+        match (self.winning, self.margin()) {
+            (Some((lowest, highest)), Some(margin)) => write!(
+                f,
+                "hold {lowest}..={highest} ms to win, best hold {} ms goes {} mm (+{margin})",
+                self.optimal_hold, self.best_distance
+            ),
+            _ => write!(
+                f,
+                "cannot win, best hold {} ms goes {} mm",
+                self.optimal_hold, self.best_distance
+            ),
+        }
+    }
+}
+
+fn too_large() -> Error {
+    Error::new(ErrorKind::InvalidData, "Distance is too large")
+}
+
+impl Boat {
+    /// Parse `acceleration=N`, `max-speed=N` and `startup=N`, unset values keeping the defaults.
+    pub fn from_args(args: &[String]) -> Result<Self, Error> {
+        let mut boat = Boat::default();
+        for arg in args {
+            let invalid = || {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid boat setting '{arg}', expected acceleration=N, max-speed=N or startup=N"),
+                )
+            };
+            let (key, value) = arg.split_once('=').ok_or_else(invalid)?;
+            let value: u128 = value.parse().map_err(|_| invalid())?;
+            match key {
+                "acceleration" => boat.acceleration = value,
+                "max-speed" => boat.max_speed = Some(value),
+                "startup" => boat.startup = value,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(boat)
+    }
+
+    fn speed(&self, hold: u128) -> Result<u128, Error> {
+        let speed = self.acceleration.checked_mul(hold).ok_or_else(too_large)?;
+        Ok(self.max_speed.map_or(speed, |max| speed.min(max)))
+    }
+
+    pub fn distance(&self, hold: u128, race_time: u128) -> Result<u128, Error> {
+        let moving = race_time.saturating_sub(hold).saturating_sub(self.startup);
+        self.speed(hold)?.checked_mul(moving).ok_or_else(too_large)
+    }
+
+    /// Shortest hold reaching the longest distance.
+    ///
+    /// Before the speed cap the distance is a parabola peaking mid race, after it the distance
+    /// only drops, so the best hold is next to the vertex or on either side of the cap.
+    fn optimal_hold(&self, race_time: u128) -> Result<u128, Error> {
+        let moving = race_time.saturating_sub(self.startup);
+        let capped_from = match (self.max_speed, self.acceleration) {
+            (_, 0) => 0,
+            (Some(max), acceleration) => max.div_ceil(acceleration),
+            (None, _) => race_time,
+        };
+
+        let mut candidates = vec![
+            0,
+            moving / 2,
+            moving.div_ceil(2),
+            capped_from.saturating_sub(1),
+            capped_from,
+        ];
+        candidates.retain(|hold| *hold <= race_time.min(capped_from));
+        let mut best = (0, 0);
+        for hold in candidates {
+            let distance = self.distance(hold, race_time)?;
+            if distance > best.1 || (distance == best.1 && hold < best.0) {
+                best = (hold, distance);
+            }
+        }
+        Ok(best.0)
+    }
+
+    /// Hold times beating the record, optimal hold and its distance.
+    ///
+    /// The distance rises up to the optimal hold and falls after it, so both ends of the
+    /// winning interval are found by bisection.
+    pub fn race(&self, race_time: u128, record: u128) -> Result<RaceReport, Error> {
+        let optimal_hold = self.optimal_hold(race_time)?;
+        let best_distance = self.distance(optimal_hold, race_time)?;
+
+        let mut winning = None;
+        if best_distance > record {
+            // First winning hold in [0, optimal_hold]
+            let (mut low, mut high) = (0, optimal_hold);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if self.distance(mid, race_time)? > record {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            let lowest = low;
+
+            // Last winning hold in [optimal_hold, race_time]
+            let (mut low, mut high) = (optimal_hold, race_time);
+            while low < high {
+                let mid = low + (high - low).div_ceil(2);
+                if self.distance(mid, race_time)? > record {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+            winning = Some((lowest, low));
+        }
+
+        Ok(RaceReport {
+            race_time,
+            record,
+            winning,
+            optimal_hold,
+            best_distance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Boat;
+    use crate::winning_holds;
+    use utils::SplitMix64;
+
+    fn brute_force(boat: &Boat, race_time: u128, record: u128) -> (Option<(u128, u128)>, u128) {
+        let distances: Vec<u128> = (0..=race_time)
+            .map(|hold| boat.distance(hold, race_time).unwrap())
+            .collect();
+        let winners: Vec<u128> = (0..=race_time)
+            .filter(|hold| distances[*hold as usize] > record)
+            .collect();
+        let best = *distances.iter().max().unwrap();
+        let optimal = distances.iter().position(|d| *d == best).unwrap() as u128;
+        (
+            winners
+                .first()
+                .map(|first| (*first, *winners.last().unwrap())),
+            optimal,
+        )
+    }
+
+    #[test]
+    fn shoud_default_to_puzzle_rules() {
+        let boat = Boat::default();
+        for race_time in 0..40 {
+            for record in 0..race_time * race_time / 4 + 2 {
+                let report = boat.race(race_time, record).unwrap();
+                assert_eq!(report.winning, winning_holds(race_time, record).unwrap());
+            }
+        }
+        let report = boat.race(30, 200).unwrap();
+        assert_eq!(report.optimal_hold, 15);
+        assert_eq!(report.margin(), Some(25));
+        assert_eq!(
+            report.to_string(),
+            "race 30 ms, record 200 mm: hold 11..=19 ms to win, best hold 15 ms goes 225 mm (+25)"
+        );
+    }
+
+    #[test]
+    fn shoud_match_brute_force_for_any_boat() {
+        let mut rng = SplitMix64(0x5eed);
+        let mut next = move |bound: usize| rng.below(bound) as u128;
+
+        for _ in 0..2000 {
+            let boat = Boat {
+                acceleration: next(4),
+                max_speed: if next(2) == 0 { None } else { Some(next(30)) },
+                startup: next(6),
+            };
+            let race_time = next(50);
+            let record = next(400);
+            let report = boat.race(race_time, record).unwrap();
+            let (winning, optimal) = brute_force(&boat, race_time, record);
+            assert_eq!(report.winning, winning, "{boat:?} {race_time} {record}");
+            assert_eq!(
+                report.optimal_hold, optimal,
+                "{boat:?} {race_time} {record}"
+            );
+        }
+    }
+
+    #[test]
+    fn shoud_parse_boat_settings() {
+        let args: Vec<String> = ["acceleration=2", "max-speed=10", "startup=3"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let boat = Boat::from_args(&args).unwrap();
+        assert_eq!(
+            boat,
+            Boat {
+                acceleration: 2,
+                max_speed: Some(10),
+                startup: 3
+            }
+        );
+        assert_eq!(boat.distance(4, 20).unwrap(), 8 * 13);
+        assert_eq!(boat.distance(8, 20).unwrap(), 10 * 9);
+        assert!(Boat::from_args(&["gravity=1".to_owned()]).is_err());
+        assert!(Boat::from_args(&["startup=-1".to_owned()]).is_err());
+    }
+}
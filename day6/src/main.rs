@@ -1,72 +1,161 @@
-use std::io::{BufRead, Error};
+mod boat;
+
+use boat::Boat;
+use std::env;
+use std::io::{BufRead, Error, ErrorKind};
 
 use utils::read_input_file;
 
-fn find_lowest_winning_opts(race_time: i64, best_distance: i64) -> i64 {
-    let mut time_to_hold = 0;
-    for n in 0..race_time {
-        if ((race_time - n) * n) > best_distance {
-            time_to_hold += 1;
-        }
+/// Inclusive range of hold times beating the best distance, if any.
+///
+/// Holding for `n` ms covers `n * (race_time - n)`, so the winning holds are strictly
+/// between the roots of `n² - race_time * n + best_distance`.
+fn winning_holds(race_time: u128, best_distance: u128) -> Result<Option<(u128, u128)>, Error> {
+    let square = race_time.checked_mul(race_time).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Race time {race_time} is too long"),
+        )
+    })?;
+    let beats = |n: u128| n * (race_time - n) > best_distance;
+
+    let discriminant = best_distance
+        .checked_mul(4)
+        .and_then(|four_distance| square.checked_sub(four_distance));
+    let Some(discriminant) = discriminant else {
+        return Ok(None);
+    };
+    // The integer root puts `lowest` on or just below the real root, step over the ties
+    let mut lowest = (race_time - discriminant.isqrt()) / 2;
+    while lowest <= race_time / 2 && !beats(lowest) {
+        lowest += 1;
+    }
+    if lowest > race_time / 2 {
+        return Ok(None);
     }
-    time_to_hold
+    // Distances are symmetric around the middle of the race
+    Ok(Some((lowest, race_time - lowest)))
 }
 
-fn part1(input: String) -> Result<i64, Error> {
-    let reader = read_input_file(input)?;
-
-    let input_lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-
-    let race_lengths: Vec<i64> = input_lines[0]
-        .split_whitespace()
-        .skip(1)
-        .filter_map(|s| s.parse().ok())
-        .collect();
+fn count_winning_holds(race_time: u128, best_distance: u128) -> Result<u128, Error> {
+    Ok(
+        winning_holds(race_time, best_distance)?
+            .map_or(0, |(lowest, highest)| highest - lowest + 1),
+    )
+}
 
-    let best_distances: Vec<i64> = input_lines[1]
-        .split_whitespace()
-        .skip(1)
-        .filter_map(|s| s.parse().ok())
-        .collect();
+/// Rest of an input line after its `label:`.
+fn after_label<'a>(lines: &'a [String], idx: usize, label: &str) -> Result<&'a str, Error> {
+    lines
+        .get(idx)
+        .and_then(|line| line.strip_prefix(label))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Line {} should start with '{label}'", idx + 1),
+            )
+        })
+}
 
-    let mut total_score = 1;
-    for i in 0..race_lengths.len() {
-        total_score *= find_lowest_winning_opts(race_lengths[i], best_distances[i]);
-    }
+fn parse_number(token: &str) -> Result<u128, Error> {
+    token
+        .parse()
+        .map_err(|_| Error::new(ErrorKind::InvalidData, format!("Invalid number '{token}'")))
+}
 
-    Ok(total_score)
+/// Read the columns of a line as a single number, failing instead of overflowing.
+fn concatenate(numbers: &str) -> Result<u128, Error> {
+    numbers
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .try_fold(0u128, |acc, c| {
+            let digit = c.to_digit(10).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidData, format!("Invalid digit '{c}'"))
+            })?;
+            acc.checked_mul(10)
+                .and_then(|acc| acc.checked_add(digit as u128))
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Number '{}' is too large", numbers.trim()),
+                    )
+                })
+        })
 }
 
-fn part2(input: String) -> Result<i64, Error> {
+fn read_lines(input: String) -> Result<Vec<String>, Error> {
     let reader = read_input_file(input)?;
+    reader.lines().collect()
+}
 
-    let input_lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
+/// Race times and records of the input, one column per race.
+fn read_races(input: String) -> Result<Vec<(u128, u128)>, Error> {
+    let input_lines = read_lines(input)?;
 
-    let race_lengths: String = input_lines[0]
+    let race_lengths: Vec<u128> = after_label(&input_lines, 0, "Time:")?
         .split_whitespace()
-        .skip(1)
-        .flat_map(|s| s.parse::<u32>().ok())
-        .map(|n| n.to_string())
-        .collect();
+        .map(parse_number)
+        .collect::<Result<_, _>>()?;
 
-    let best_distances: String = input_lines[1]
+    let best_distances: Vec<u128> = after_label(&input_lines, 1, "Distance:")?
         .split_whitespace()
-        .skip(1)
-        .flat_map(|s| s.parse::<u32>().ok())
-        .map(|n| n.to_string())
-        .collect();
+        .map(parse_number)
+        .collect::<Result<_, _>>()?;
+
+    if race_lengths.len() != best_distances.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} race times for {} distances",
+                race_lengths.len(),
+                best_distances.len()
+            ),
+        ));
+    }
+
+    Ok(race_lengths.into_iter().zip(best_distances).collect())
+}
 
-    let race_length: i64 = race_lengths.parse().unwrap_or(0);
-    let best_distance: i64 = best_distances.parse().unwrap_or(0);
+fn part1(input: String) -> Result<u128, Error> {
+    let mut total_score: u128 = 1;
+    for (race_length, best_distance) in read_races(input)? {
+        total_score = total_score
+            .checked_mul(count_winning_holds(race_length, best_distance)?)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Score is too large"))?;
+    }
 
-    println!("{} {}", race_length, best_distance);
-    let total_score = find_lowest_winning_opts(race_length, best_distance);
     Ok(total_score)
 }
 
+fn part2(input: String) -> Result<u128, Error> {
+    let input_lines = read_lines(input)?;
+
+    let race_length = concatenate(after_label(&input_lines, 0, "Time:")?)?;
+    let best_distance = concatenate(after_label(&input_lines, 1, "Distance:")?)?;
+
+    count_winning_holds(race_length, best_distance)
+}
+
 fn main() -> Result<(), Error> {
     println!("Hello advent of code day 6 !");
 
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "boat") {
+        let input = args
+            .get(2)
+            .cloned()
+            .unwrap_or("day6/src/resources/input.txt".to_owned());
+        let boat = Boat::from_args(args.get(3..).unwrap_or_default())?;
+        let mut ways: u128 = 1;
+        for (race_time, record) in read_races(input)? {
+            let report = boat.race(race_time, record)?;
+            ways = ways.saturating_mul(report.ways_to_win());
+            println!("{report}");
+        }
+        println!("Product of the ways to win: {ways}");
+        return Ok(());
+    }
+
     let result_p1 = part1("day6/src/resources/input.txt".to_owned())?;
     let result_p2 = part2("day6/src/resources/input.txt".to_owned())?;
 
@@ -77,13 +166,16 @@ fn main() -> Result<(), Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2};
+    use crate::{concatenate, count_winning_holds, part1, part2, winning_holds};
 
     #[test]
     fn shoud_get_score() -> Result<(), String> {
         let result = part1("src/resources/test-input.txt".to_owned())
             .map_err(|e| format!("Test failed with error: {:?}", e))?;
         assert_eq!(result, 288);
+        let result = part1("src/resources/input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 227850);
         Ok(())
     }
 
@@ -91,8 +183,45 @@ mod tests {
     fn shoud_get_score_p2() -> Result<(), String> {
         let result = part2("src/resources/test-input.txt".to_owned())
             .map_err(|e| format!("Test failed with error: {:?}", e))?;
-        // assert_eq!(result, 288);
-        println!("{result}");
+        assert_eq!(result, 71503);
+        let result = part2("src/resources/input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 42948149);
         Ok(())
     }
+
+    #[test]
+    fn shoud_match_brute_force_at_boundaries() {
+        // 30 ms for 200 mm: holding 10 or 20 only ties, the roots are exact
+        assert_eq!(winning_holds(30, 200).unwrap(), Some((11, 19)));
+        assert_eq!(winning_holds(4, 4).unwrap(), None);
+        assert_eq!(winning_holds(4, 3).unwrap(), Some((2, 2)));
+        assert_eq!(winning_holds(0, 0).unwrap(), None);
+
+        for race_time in 0..60u128 {
+            for best_distance in 0..race_time * race_time / 4 + 2 {
+                let expected = (0..=race_time)
+                    .filter(|n| n * (race_time - n) > best_distance)
+                    .count() as u128;
+                assert_eq!(
+                    count_winning_holds(race_time, best_distance).unwrap(),
+                    expected,
+                    "{race_time} {best_distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shoud_report_overflows() {
+        assert_eq!(concatenate(" 7  15   30").unwrap(), 71530);
+        assert!(concatenate("4 8 15 16 23 42").is_ok());
+        let error = concatenate(&"9".repeat(40)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Number '{}' is too large", "9".repeat(40))
+        );
+        assert!(concatenate("12 x4").is_err());
+        assert!(count_winning_holds(u128::MAX / 2, 1).is_err());
+    }
 }
@@ -0,0 +1,63 @@
+use crate::hand::Hand;
+use crate::packed::{total_winnings, PackedRanker};
+use crate::ruleset::{Ruleset, ALL_CARDS};
+use crate::Card;
+use std::io::{Error, ErrorKind};
+use std::time::Instant;
+use utils::SplitMix64;
+
+/// Deterministic random hands with their bids.
+fn generate_hands(count: usize, hand_size: usize) -> Vec<(Vec<Card>, u64)> {
+    let mut rng = SplitMix64(2023);
+
+    (0..count)
+        .map(|_| {
+            let cards = (0..hand_size).map(|_| ALL_CARDS[rng.below(13)]).collect();
+            (cards, rng.below(1000) as u64)
+        })
+        .collect()
+}
+
+pub fn bench(count: usize, ruleset: &Ruleset) -> Result<(), Error> {
+    let generated = generate_hands(count, ruleset.hand_size());
+    println!("{count} hands of {} cards", ruleset.hand_size());
+
+    let start = Instant::now();
+    let mut hands = Vec::with_capacity(count);
+    for (cards, bid) in &generated {
+        hands.push(Hand::new(cards.clone(), *bid, ruleset)?);
+    }
+    hands.sort();
+    let classifier: u64 = hands
+        .iter()
+        .enumerate()
+        .map(|(idx, hand)| (idx + 1) as u64 * hand.bid)
+        .sum();
+    let classifier_time = start.elapsed();
+    println!("classifier: {classifier} in {classifier_time:?}");
+
+    let start = Instant::now();
+    let ranker = PackedRanker::new(ruleset)?;
+    let table_time = start.elapsed();
+    let mut scored: Vec<(u64, u64)> = generated
+        .iter()
+        .map(|(cards, bid)| (ranker.score(ranker.encode(cards)), *bid))
+        .collect();
+    let packed = total_winnings(&mut scored);
+    let packed_time = start.elapsed();
+    println!("packed:     {packed} in {packed_time:?} (table built in {table_time:?})");
+
+    if classifier != packed {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Packed ranking {packed} disagrees with the classifier {classifier}"),
+        ));
+    }
+    if classifier_time > packed_time {
+        println!(
+            "packed ranking is {:.1}x faster",
+            classifier_time.as_secs_f64() / packed_time.as_secs_f64()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use crate::ruleset::Ruleset;
+use crate::{char_to_card, Card};
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+/// Card counts of a hand from the largest one, e.g. `[3, 2]` for a full house.
+///
+/// Comparing the counts in order ranks the usual categories for any hand size: a larger
+/// group wins first, then the size of the next group.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Strength(Vec<u8>);
+
+impl Strength {
+    pub fn new(mut counts: Vec<u8>) -> Self {
+        counts.retain(|count| *count > 0);
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        Strength(counts)
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0.as_slice() {
+            [5] => "five of a kind",
+            [4, 1] => "four of a kind",
+            [3, 2] => "full house",
+            [3, 1, 1] => "three of a kind",
+            [2, 2, 1] => "two pair",
+            [2, 1, 1, 1] => "one pair",
+            [1, 1, 1, 1, 1] => "high card",
+            counts => {
+                let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+                return write!(f, "{}", counts.join("+"));
+            }
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Hand of cards with its bid, ranked under a ruleset.
+/// Hands compare by rank only, two hands holding the same cards are equal whatever their bids.
+#[derive(Debug, Clone)]
+pub struct Hand {
+    pub cards: Vec<Card>,
+    pub bid: u64,
+    pub strength: Strength,
+    /// Card the wilds counted as, if the hand holds any.
+    pub wild_target: Option<Card>,
+    /// Rank of each card under the ruleset, breaking ties between equal strengths.
+    ranks: Vec<u8>,
+}
+
+impl Hand {
+    pub fn new(cards: Vec<Card>, bid: u64, ruleset: &Ruleset) -> Result<Self, Error> {
+        if cards.len() != ruleset.hand_size() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Hand has {} cards, expected {}",
+                    cards.len(),
+                    ruleset.hand_size()
+                ),
+            ));
+        }
+        Ok(Hand {
+            strength: ruleset.strength(&cards),
+            wild_target: ruleset.wild_target(&cards),
+            ranks: cards.iter().map(|card| ruleset.rank(*card)).collect(),
+            cards,
+            bid,
+        })
+    }
+
+    /// Parse a `<cards> <bid>` line.
+    pub fn parse(line: &str, ruleset: &Ruleset) -> Result<Self, Error> {
+        let invalid = |reason: String| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid hand '{line}': {reason}"),
+            )
+        };
+
+        let [cards, bid] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(invalid("expected '<cards> <bid>'".to_owned()));
+        };
+        let cards = cards
+            .chars()
+            .map(|ch| char_to_card(ch).ok_or_else(|| invalid(format!("unknown card '{ch}'"))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bid = bid
+            .parse()
+            .map_err(|_| invalid(format!("bid '{bid}' is not a number")))?;
+        Hand::new(cards, bid, ruleset).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.strength
+            .cmp(&other.strength)
+            .then_with(|| self.ranks.cmp(&other.ranks))
+    }
+}
+
+impl PartialEq for Hand {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Hand {}
+
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hand, Strength};
+    use crate::ruleset::Ruleset;
+
+    #[test]
+    fn shoud_rank_categories_by_signature() {
+        let ordered = [
+            vec![1, 1, 1, 1, 1],
+            vec![2, 1, 1, 1],
+            vec![2, 2, 1],
+            vec![3, 1, 1],
+            vec![3, 2],
+            vec![4, 1],
+            vec![5],
+        ];
+        let strengths: Vec<Strength> = ordered.into_iter().map(Strength::new).collect();
+        assert!(strengths.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(Strength::new(vec![2, 3]).to_string(), "full house");
+        assert_eq!(Strength::new(vec![3, 3]).to_string(), "3+3");
+    }
+
+    #[test]
+    fn shoud_rank_larger_hands() {
+        let ruleset = Ruleset::standard().with_hand_size(7).unwrap();
+        let hand = |line: &str| Hand::parse(line, &ruleset).unwrap();
+
+        assert_eq!(hand("AAA2233 1").strength, Strength::new(vec![2, 3, 2]));
+        assert!(hand("2222333 1") > hand("AAAKKQQ 1"));
+        assert!(hand("2223333 1") > hand("2222333 1"));
+        assert!(hand("AAAKKQJ 1") < hand("2223344 1"));
+        assert_eq!(hand("AAAKKQJ 1"), hand("AAAKKQJ 2"));
+    }
+
+    #[test]
+    fn shoud_reject_invalid_hands() {
+        let ruleset = Ruleset::joker();
+        let error = |line: &str| Hand::parse(line, &ruleset).unwrap_err().to_string();
+
+        assert_eq!(
+            error("32T3X 765"),
+            "Invalid hand '32T3X 765': unknown card 'X'"
+        );
+        assert_eq!(
+            error("32T3 765"),
+            "Invalid hand '32T3 765': Hand has 4 cards, expected 5"
+        );
+        assert_eq!(
+            error("32T3K 7a"),
+            "Invalid hand '32T3K 7a': bid '7a' is not a number"
+        );
+        assert_eq!(
+            error("32T3K"),
+            "Invalid hand '32T3K': expected '<cards> <bid>'"
+        );
+        assert!(Hand::parse("32T3K 765", &ruleset).is_ok());
+    }
+}
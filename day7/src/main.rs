@@ -1,7 +1,15 @@
+mod bench;
+mod hand;
+mod packed;
+mod report;
+mod ruleset;
+
+use hand::Hand;
+use report::ReportFormat;
+use ruleset::Ruleset;
 use std::{
-    cmp::Ordering,
-    collections::HashMap,
-    io::{BufRead, Error},
+    env, fs,
+    io::{BufRead, Error, ErrorKind},
 };
 use utils::read_input_file;
 
@@ -16,26 +24,15 @@ enum Card {
     Eight,
     Nine,
     Ten,
-    Joker,
+    Jack,
     Queen,
     King,
     Ace,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
-enum Strength {
-    FiveKind = 6,
-    FourKind = 5,
-    FullHouse = 4,
-    ThreeKind = 3,
-    TwoPair = 2,
-    OnePair = 1,
-    HighCard = 0,
-}
-
 fn char_to_card(ch: char) -> Option<Card> {
     match ch.to_ascii_uppercase() {
-        'J' => Some(Card::Joker),
+        'J' => Some(Card::Jack),
         '2' => Some(Card::Two),
         '3' => Some(Card::Three),
         '4' => Some(Card::Four),
@@ -52,127 +49,142 @@ fn char_to_card(ch: char) -> Option<Card> {
     }
 }
 
-fn card_to_val(ch: char) -> u8 {
-    match ch {
-        '0'..='9' => ch.to_digit(10).unwrap() as u8,
-        'T' => 10,
-        'J' => 0,
-        'Q' => 12,
-        'K' => 13,
-        'A' => 14,
-        _ => unreachable!(),
+fn card_to_char(card: Card) -> char {
+    match card {
+        Card::Two => '2',
+        Card::Three => '3',
+        Card::Four => '4',
+        Card::Five => '5',
+        Card::Six => '6',
+        Card::Seven => '7',
+        Card::Eight => '8',
+        Card::Nine => '9',
+        Card::Ten => 'T',
+        Card::Jack => 'J',
+        Card::Queen => 'Q',
+        Card::King => 'K',
+        Card::Ace => 'A',
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Hand {
-    hand: String,
-    strength: Strength,
-    bid: i32,
-}
+/// Hands of the input sorted from the weakest to the strongest.
+fn read_hands(input: String, ruleset: &Ruleset) -> Result<Vec<Hand>, Error> {
+    let reader = read_input_file(input)?;
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.hand == other.hand {
-            return Ordering::Equal;
-        }
-        let self_hand = self.strength;
-        let other_hand = other.strength;
-        let mut self_chars = self.hand.chars();
-        let mut other_chars = other.hand.chars();
-
-        let mut ordering = self_hand.cmp(&other_hand);
-        while ordering == Ordering::Equal {
-            ordering = card_to_val(self_chars.next().unwrap())
-                .cmp(&card_to_val(other_chars.next().unwrap()))
+    let mut hands = Vec::<Hand>::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
         }
-        ordering
+        let hand = Hand::parse(&line, ruleset)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Line {}: {e}", idx + 1)))?;
+        hands.push(hand);
     }
-}
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+    hands.sort();
+    Ok(hands)
 }
 
-fn count_occurrences(arr: &[Card]) -> Strength {
-    let mut count_map = HashMap::new();
+fn process(input: String, ruleset: &Ruleset) -> Result<u64, Error> {
+    let hands = read_hands(input, ruleset)?;
 
-    for &num in arr {
-        let count = count_map.entry(num).or_insert(0);
-        *count += 1;
+    let mut res = 0;
+    for (i, hand) in hands.into_iter().enumerate() {
+        res += (i + 1) as u64 * hand.bid;
     }
+    Ok(res)
+}
 
-    let jokers = count_map.remove(&Card::Joker).unwrap_or(0);
-
-    let mut counts = count_map.clone().into_values().collect::<Vec<usize>>();
+/// Remove a `name=value` argument, returning its value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    let idx = args.iter().position(|arg| arg.starts_with(&prefix))?;
+    Some(args.remove(idx)[prefix.len()..].to_owned())
+}
 
-    counts.sort();
-    counts.reverse();
+fn main() -> Result<(), Error> {
+    println!("Hello advent of code day 7 !");
 
-    // add jokers to highest count
-    let with_jokers = counts.first().unwrap_or(&0) + jokers;
-    match with_jokers {
-        5 => Strength::FiveKind,
-        4 => Strength::FourKind,
-        3 => {
-            if counts[1] == 2 {
-                Strength::FullHouse
-            } else {
-                Strength::ThreeKind
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let input = take_option(&mut args, "input")
+        .unwrap_or_else(|| "day7/src/resources/input.txt".to_owned());
+    let hand_size: Option<usize> = match take_option(&mut args, "size") {
+        Some(size) => Some(size.parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid hand size '{size}'"),
+            )
+        })?),
+        None => None,
+    };
+    let output = take_option(&mut args, "output");
+    let bench_count = match args.first().map(String::as_str) {
+        Some("bench") => {
+            args.remove(0);
+            match args.first().and_then(|count| count.parse::<usize>().ok()) {
+                Some(count) => {
+                    args.remove(0);
+                    Some(count)
+                }
+                None => Some(1_000_000),
             }
         }
-        2 => {
-            if counts[1] == 2 {
-                Strength::TwoPair
-            } else {
-                Strength::OnePair
+        _ => None,
+    };
+    let report = match args.first().map(String::as_str) {
+        Some("report") => {
+            args.remove(0);
+            // The format is optional, anything but a ruleset name must be one
+            match args.first().map(String::as_str) {
+                None | Some("standard" | "joker" | "custom") => Some(ReportFormat::Text),
+                Some(format) => {
+                    let format = format.parse()?;
+                    args.remove(0);
+                    Some(format)
+                }
             }
         }
-        1 => Strength::HighCard,
-        _ => unreachable!(),
-    }
-}
-
-fn process(input: String) -> Result<i32, Error> {
-    let reader = read_input_file(input)?;
-
-    let input_lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-    let mut hands = Vec::<Hand>::new();
-    for line in input_lines {
-        let mut slices = line.split_whitespace();
-        let hand = slices.next().unwrap();
-        let bid = slices.next().unwrap().parse::<i32>().unwrap();
-
-        let mut cards_hand: [Card; 5] = [Card::Ace; 5];
-        for (idx, ch) in hand.chars().enumerate() {
-            cards_hand[idx] = char_to_card(ch).unwrap();
-        }
-        let strength = count_occurrences(&cards_hand);
-
-        let hand = Hand {
-            hand: hand.to_owned(),
-            strength,
-            bid,
+        _ => None,
+    };
+    if !args.is_empty() || hand_size.is_some() || report.is_some() || bench_count.is_some() {
+        let mut ruleset = if args.is_empty() {
+            Ruleset::joker()
+        } else {
+            Ruleset::from_args(&args)?
         };
-        hands.push(hand);
-    }
-
-    hands.sort();
-    let mut res = 0;
-    for (i, hand) in hands.into_iter().enumerate() {
-        res += (i + 1) as i32 * hand.bid;
+        if let Some(hand_size) = hand_size {
+            ruleset = ruleset.with_hand_size(hand_size)?;
+        }
+        if let Some(count) = bench_count {
+            bench::bench(count, &ruleset)?;
+            println!(
+                "The packed result is {}",
+                packed::process_packed(input, &ruleset)?
+            );
+            return Ok(());
+        }
+        if let Some(format) = report {
+            let (rows, total) = report::ranking(&read_hands(input, &ruleset)?);
+            let rendered = report::render(&rows, total, format);
+            match output {
+                Some(output) => {
+                    fs::write(&output, rendered)?;
+                    println!("Report written to {output}");
+                }
+                None => print!("{rendered}"),
+            }
+            return Ok(());
+        }
+        let result = process(input, &ruleset)?;
+        println!("The result is {}", result);
+        return Ok(());
     }
-    Ok(res)
-}
-
-fn main() -> Result<(), Error> {
-    println!("Hello advent of code day 7 !");
 
-    let result = process("day7/src/resources/input.txt".to_owned())?;
+    let result_p1 = process(input.clone(), &Ruleset::standard())?;
+    let result_p2 = process(input, &Ruleset::joker())?;
 
-    println!("The result is {}", result);
+    println!("The result is p1 {} p2 {}", result_p1, result_p2);
 
     Ok(())
 }
@@ -180,12 +192,30 @@ fn main() -> Result<(), Error> {
 #[cfg(test)]
 mod tests {
     use crate::process;
+    use crate::ruleset::Ruleset;
 
     #[test]
     fn shoud_get_score() -> Result<(), String> {
-        let result = process("src/resources/test-input.txt".to_owned())
+        let result = process("src/resources/test-input.txt".to_owned(), &Ruleset::joker())
             .map_err(|e| format!("Test failed with error: {:?}", e))?;
         assert_eq!(result, 5905);
+        let result = process("src/resources/input.txt".to_owned(), &Ruleset::joker())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 250384185);
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_get_score_with_jacks() -> Result<(), String> {
+        let result = process(
+            "src/resources/test-input.txt".to_owned(),
+            &Ruleset::standard(),
+        )
+        .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 6440);
+        let result = process("src/resources/input.txt".to_owned(), &Ruleset::standard())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 251545216);
         Ok(())
     }
 }
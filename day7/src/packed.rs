@@ -0,0 +1,256 @@
+use crate::hand::Strength;
+use crate::ruleset::{Ruleset, WildUpgrade, ALL_CARDS};
+use crate::{char_to_card, Card};
+use std::io::{BufRead, Error, ErrorKind};
+use utils::read_input_file;
+
+/// Largest hand the strength table is built for, the table holding `(size + 1)^size` entries.
+pub const MAX_PACKED_SIZE: usize = 7;
+
+/// Ranks hands encoded as integers, without allocating.
+///
+/// A hand is packed as the rank of each card in 4 bits, the first card in the highest bits.
+/// Its score puts the strength above the packed ranks, so comparing two scores compares
+/// the hands. The strength comes from a table indexed by how many ranks appear once,
+/// twice, ... which is all a count signature depends on.
+pub struct PackedRanker {
+    /// Rank of each card, indexed by the `Card` discriminant.
+    ranks: [u8; 13],
+    /// Bit `r` is set when the card of rank `r` is wild.
+    wilds: u16,
+    upgrade: WildUpgrade,
+    hand_size: usize,
+    /// Strength position of every histogram of counts.
+    table: Vec<u8>,
+}
+
+/// Partitions of `remaining` in parts no larger than `largest`, from the largest part.
+fn partitions(remaining: u8, largest: u8, current: &mut Vec<u8>, found: &mut Vec<Vec<u8>>) {
+    if remaining == 0 {
+        found.push(current.clone());
+        return;
+    }
+    for part in (1..=largest.min(remaining)).rev() {
+        current.push(part);
+        partitions(remaining - part, part, current, found);
+        current.pop();
+    }
+}
+
+impl PackedRanker {
+    pub fn new(ruleset: &Ruleset) -> Result<Self, Error> {
+        let hand_size = ruleset.hand_size();
+        if hand_size == 0 || hand_size > MAX_PACKED_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Packed hands hold 1 to {MAX_PACKED_SIZE} cards, not {hand_size}"),
+            ));
+        }
+
+        let mut ranks = [0; 13];
+        let mut wilds = 0;
+        for card in ALL_CARDS {
+            ranks[card as usize] = ruleset.rank(card);
+            if ruleset.is_wild(card) {
+                wilds |= 1 << ruleset.rank(card);
+            }
+        }
+
+        // Every count signature of the hand size, sorted the way `Strength` compares them
+        let mut signatures = Vec::new();
+        partitions(
+            hand_size as u8,
+            hand_size as u8,
+            &mut Vec::new(),
+            &mut signatures,
+        );
+        signatures.sort_by_key(|counts| Strength::new(counts.clone()));
+
+        let base = hand_size + 1;
+        let mut table = vec![0; base.pow(hand_size as u32)];
+        for (position, counts) in signatures.iter().enumerate() {
+            let key: usize = counts.iter().map(|count| base.pow(*count as u32 - 1)).sum();
+            table[key] = position as u8;
+        }
+
+        Ok(PackedRanker {
+            ranks,
+            wilds,
+            upgrade: ruleset.upgrade(),
+            hand_size,
+            table,
+        })
+    }
+
+    pub fn hand_size(&self) -> usize {
+        self.hand_size
+    }
+
+    pub fn encode(&self, cards: &[Card]) -> u32 {
+        cards.iter().fold(0, |packed, card| {
+            packed << 4 | self.ranks[*card as usize] as u32
+        })
+    }
+
+    /// Strength position of the packed hand, 0 being the weakest signature.
+    pub fn strength(&self, packed: u32) -> u8 {
+        let mut counts = [0u8; 13];
+        let mut wild_count = 0;
+        for idx in 0..self.hand_size {
+            let rank = (packed >> (4 * idx)) & 0xf;
+            if self.wilds & (1 << rank) != 0 {
+                wild_count += 1;
+            } else {
+                counts[rank as usize] += 1;
+            }
+        }
+
+        if wild_count > 0 {
+            // Same choice as `Ruleset::wild_target`, the strongest card when all are wild
+            let target = match self.upgrade {
+                WildUpgrade::MostFrequent => (0..13).max_by_key(|rank| (counts[*rank], *rank)),
+                WildUpgrade::HighestCard => (0..13).rev().find(|rank| counts[*rank] > 0),
+            };
+            let target = target.filter(|rank| counts[*rank] > 0).unwrap_or(12);
+            counts[target] += wild_count;
+        }
+
+        let base = self.hand_size + 1;
+        let key: usize = counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| base.pow(*count as u32 - 1))
+            .sum();
+        self.table[key]
+    }
+
+    /// Comparable score: the strength position above the packed ranks.
+    pub fn score(&self, packed: u32) -> u64 {
+        (self.strength(packed) as u64) << 32 | packed as u64
+    }
+}
+
+/// Same total as `process`, ranking `(score, bid)` pairs.
+pub fn process_packed(input: String, ruleset: &Ruleset) -> Result<u64, Error> {
+    let ranker = PackedRanker::new(ruleset)?;
+    let reader = read_input_file(input)?;
+
+    let mut scored: Vec<(u64, u64)> = Vec::new();
+    let mut cards = Vec::with_capacity(ranker.hand_size());
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Line {}: invalid hand '{line}'", idx + 1),
+            )
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(hand), Some(bid), None) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(invalid());
+        };
+        cards.clear();
+        for ch in hand.chars() {
+            cards.push(char_to_card(ch).ok_or_else(invalid)?);
+        }
+        if cards.len() != ranker.hand_size() {
+            return Err(invalid());
+        }
+        let bid = bid.parse().map_err(|_| invalid())?;
+        scored.push((ranker.score(ranker.encode(&cards)), bid));
+    }
+
+    Ok(total_winnings(&mut scored))
+}
+
+/// Winnings of `(score, bid)` pairs ranked by score.
+pub fn total_winnings(scored: &mut [(u64, u64)]) -> u64 {
+    // Stable on the score only, equal hands keep their input order like in `process`
+    scored.sort_by_key(|(score, _)| *score);
+    scored
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, bid))| (idx + 1) as u64 * bid)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{process_packed, PackedRanker};
+    use crate::hand::Hand;
+    use crate::process;
+    use crate::ruleset::{Ruleset, ALL_CARDS};
+    use utils::SplitMix64;
+
+    fn rulesets() -> Vec<Ruleset> {
+        let custom: Vec<String> = ["custom", "J23456789TQKA", "J2", "highest-card"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        vec![
+            Ruleset::standard(),
+            Ruleset::joker(),
+            Ruleset::from_args(&custom).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn shoud_match_hand_classifier() {
+        let mut rng = SplitMix64(0x5eed);
+
+        for hand_size in [3, 5, 6, 7] {
+            for ruleset in rulesets() {
+                let ruleset = ruleset.with_hand_size(hand_size).unwrap();
+                let ranker = PackedRanker::new(&ruleset).unwrap();
+                // Few distinct cards so that pairs, fulls and wilds show up often
+                let hands: Vec<Hand> = (0..400)
+                    .map(|_| {
+                        let cards = (0..hand_size)
+                            .map(|_| ALL_CARDS[[0, 1, 3, 9, 10, 12][rng.below(6)]])
+                            .collect();
+                        Hand::new(cards, 1, &ruleset).unwrap()
+                    })
+                    .collect();
+                let scores: Vec<u64> = hands
+                    .iter()
+                    .map(|hand| ranker.score(ranker.encode(&hand.cards)))
+                    .collect();
+
+                for i in 0..hands.len() {
+                    for j in 0..hands.len() {
+                        assert_eq!(
+                            hands[i].cmp(&hands[j]),
+                            scores[i].cmp(&scores[j]),
+                            "{:?} {:?}",
+                            hands[i].cards,
+                            hands[j].cards
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shoud_match_process() -> Result<(), String> {
+        for input in ["src/resources/test-input.txt", "src/resources/input.txt"] {
+            for ruleset in rulesets() {
+                let packed = process_packed(input.to_owned(), &ruleset)
+                    .map_err(|e| format!("Test failed with error: {:?}", e))?;
+                let expected = process(input.to_owned(), &ruleset)
+                    .map_err(|e| format!("Test failed with error: {:?}", e))?;
+                assert_eq!(packed, expected);
+            }
+        }
+        assert!(PackedRanker::new(&Ruleset::standard().with_hand_size(8).unwrap()).is_err());
+
+        let input = "src/resources/test-input-invalid.txt";
+        let error = process_packed(input.to_owned(), &Ruleset::standard()).unwrap_err();
+        assert_eq!(error.to_string(), "Line 3: invalid hand 'T55J5765'");
+        assert!(process(input.to_owned(), &Ruleset::standard()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::card_to_char;
+use crate::hand::Hand;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid report format '{s}', expected text, csv or json"),
+            )),
+        }
+    }
+}
+
+/// Why a hand ended up where it is in the ranking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub rank: usize,
+    pub cards: String,
+    pub category: String,
+    /// Card the wilds were counted as, if the hand holds any.
+    pub wild_as: Option<char>,
+    pub bid: u64,
+    pub winnings: u64,
+}
+
+/// One row per hand, from the weakest to the strongest, with the total winnings.
+pub fn ranking(hands: &[Hand]) -> (Vec<Row>, u64) {
+    let mut sorted: Vec<&Hand> = hands.iter().collect();
+    sorted.sort();
+
+    let rows: Vec<Row> = sorted
+        .into_iter()
+        .enumerate()
+        .map(|(idx, hand)| Row {
+            rank: idx + 1,
+            cards: hand.cards.iter().map(|card| card_to_char(*card)).collect(),
+            category: hand.strength.to_string(),
+            wild_as: hand.wild_target.map(card_to_char),
+            bid: hand.bid,
+            winnings: (idx + 1) as u64 * hand.bid,
+        })
+        .collect();
+    let total = rows.iter().map(|row| row.winnings).sum();
+    (rows, total)
+}
+
+pub fn render(rows: &[Row], total: u64, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Text => {
+            let mut output = String::new();
+            for row in rows {
+                let wild = row
+                    .wild_as
+                    .map_or(String::new(), |card| format!(" (wilds as {card})"));
+                output.push_str(&format!(
+                    "#{:<5} {} {}{wild}: bid {} wins {}\n",
+                    row.rank, row.cards, row.category, row.bid, row.winnings
+                ));
+            }
+            output.push_str(&format!("Total winnings: {total}\n"));
+            output
+        }
+        ReportFormat::Csv => {
+            let mut output = String::from("rank,cards,category,wild_as,bid,winnings\n");
+            for row in rows {
+                output.push_str(&format!(
+                    "{},{},{},{},{},{}\n",
+                    row.rank,
+                    row.cards,
+                    row.category,
+                    row.wild_as.map_or(String::new(), String::from),
+                    row.bid,
+                    row.winnings
+                ));
+            }
+            output.push_str(&format!("total,,,,,{total}\n"));
+            output
+        }
+        ReportFormat::Json => {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    format!(
+                        "    {{\"rank\": {}, \"cards\": \"{}\", \"category\": \"{}\", \"wild_as\": {}, \"bid\": {}, \"winnings\": {}}}",
+                        row.rank,
+                        row.cards,
+                        row.category,
+                        row.wild_as.map_or("null".to_owned(), |card| format!("\"{card}\"")),
+                        row.bid,
+                        row.winnings
+                    )
+                })
+                .collect();
+            format!(
+                "{{\n  \"hands\": [\n{}\n  ],\n  \"total\": {total}\n}}\n",
+                rows.join(",\n")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ranking, render, ReportFormat};
+    use crate::ruleset::Ruleset;
+    use crate::{process, read_hands};
+
+    #[test]
+    fn shoud_match_process_total() -> Result<(), String> {
+        for input in ["src/resources/test-input.txt", "src/resources/input.txt"] {
+            for ruleset in [Ruleset::standard(), Ruleset::joker()] {
+                let hands = read_hands(input.to_owned(), &ruleset)
+                    .map_err(|e| format!("Test failed with error: {:?}", e))?;
+                let expected = process(input.to_owned(), &ruleset)
+                    .map_err(|e| format!("Test failed with error: {:?}", e))?;
+                let (rows, total) = ranking(&hands);
+                assert_eq!(total, expected);
+                assert!(rows.windows(2).all(|pair| pair[0].rank + 1 == pair[1].rank));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shoud_export_csv_and_json() -> Result<(), String> {
+        let hands = read_hands("src/resources/test-input.txt".to_owned(), &Ruleset::joker())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        let (rows, total) = ranking(&hands);
+
+        let csv = render(&rows, total, ReportFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "rank,cards,category,wild_as,bid,winnings");
+        assert_eq!(lines[1], "1,32T3K,one pair,,765,765");
+        assert_eq!(lines[5], "5,KTJJT,four of a kind,T,220,1100");
+        assert_eq!(lines[6], "total,,,,,5905");
+
+        let json = render(&rows, total, ReportFormat::Json);
+        assert!(json.contains(
+            r#"{"rank": 4, "cards": "QQQJA", "category": "four of a kind", "wild_as": "Q", "bid": 483, "winnings": 1932}"#
+        ));
+        assert!(json.ends_with("  \"total\": 5905\n}\n"));
+        assert!("xml".parse::<ReportFormat>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use crate::hand::Strength;
+use crate::{char_to_card, Card};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// Which card the wild cards of a hand pretend to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WildUpgrade {
+    /// Join the most frequent card, the highest ranked one on ties.
+    MostFrequent,
+    /// Copy the highest ranked card of the hand.
+    HighestCard,
+}
+
+impl FromStr for WildUpgrade {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "most-frequent" => Ok(WildUpgrade::MostFrequent),
+            "highest-card" => Ok(WildUpgrade::HighestCard),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid wild upgrade '{s}', expected 'most-frequent' or 'highest-card'"),
+            )),
+        }
+    }
+}
+
+/// Card ranks used to break ties, and the wild cards with how they upgrade a hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruleset {
+    /// Rank of every card, 0 being the weakest.
+    ranks: HashMap<Card, u8>,
+    wilds: Vec<Card>,
+    upgrade: WildUpgrade,
+    hand_size: usize,
+}
+
+pub const ALL_CARDS: [Card; 13] = [
+    Card::Two,
+    Card::Three,
+    Card::Four,
+    Card::Five,
+    Card::Six,
+    Card::Seven,
+    Card::Eight,
+    Card::Nine,
+    Card::Ten,
+    Card::Jack,
+    Card::Queen,
+    Card::King,
+    Card::Ace,
+];
+
+impl Ruleset {
+    /// Build a ruleset from the cards listed from the weakest to the strongest.
+    pub fn new(order: &[Card], wilds: &[Card], upgrade: WildUpgrade) -> Result<Self, Error> {
+        let mut ranks = HashMap::new();
+        for (rank, card) in order.iter().enumerate() {
+            if ranks.insert(*card, rank as u8).is_some() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Card {card:?} is ranked twice"),
+                ));
+            }
+        }
+        if let Some(missing) = ALL_CARDS.iter().find(|card| !ranks.contains_key(card)) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Card {missing:?} has no rank"),
+            ));
+        }
+        Ok(Ruleset {
+            ranks,
+            wilds: wilds.to_vec(),
+            upgrade,
+            hand_size: 5,
+        })
+    }
+
+    /// Part 1: `J` is a Jack, ranked between Ten and Queen.
+    pub fn standard() -> Self {
+        Ruleset::new(&ALL_CARDS, &[], WildUpgrade::MostFrequent).unwrap()
+    }
+
+    /// Part 2: `J` is a Joker, the weakest card but wild.
+    pub fn joker() -> Self {
+        let mut order = vec![Card::Jack];
+        order.extend(ALL_CARDS.iter().filter(|card| **card != Card::Jack));
+        Ruleset::new(&order, &[Card::Jack], WildUpgrade::MostFrequent).unwrap()
+    }
+
+    /// `standard`, `joker` or `custom <order> [<wilds>] [<upgrade>]`, where the order lists the
+    /// cards from the weakest to the strongest, e.g. `custom 23456789TJQKA 2 highest-card`.
+    pub fn from_args(args: &[String]) -> Result<Self, Error> {
+        match args {
+            [name] if name == "standard" => Ok(Ruleset::standard()),
+            [name] if name == "joker" => Ok(Ruleset::joker()),
+            [name, order, rest @ ..] if name == "custom" && rest.len() <= 2 => {
+                let wilds = match rest.first() {
+                    Some(wilds) => parse_cards(wilds)?,
+                    None => Vec::new(),
+                };
+                let upgrade = match rest.get(1) {
+                    Some(upgrade) => upgrade.parse()?,
+                    None => WildUpgrade::MostFrequent,
+                };
+                Ruleset::new(&parse_cards(order)?, &wilds, upgrade)
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Expected standard, joker or custom <order> [<wilds>] [<upgrade>]",
+            )),
+        }
+    }
+
+    /// Card counts are kept as `u8`, so a hand holds 1 to 255 cards.
+    pub fn with_hand_size(mut self, hand_size: usize) -> Result<Self, Error> {
+        if hand_size == 0 || hand_size > u8::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Hands hold 1 to {} cards, not {hand_size}", u8::MAX),
+            ));
+        }
+        self.hand_size = hand_size;
+        Ok(self)
+    }
+
+    pub fn hand_size(&self) -> usize {
+        self.hand_size
+    }
+
+    pub fn upgrade(&self) -> WildUpgrade {
+        self.upgrade
+    }
+
+    pub fn rank(&self, card: Card) -> u8 {
+        self.ranks[&card]
+    }
+
+    pub fn is_wild(&self, card: Card) -> bool {
+        self.wilds.contains(&card)
+    }
+
+    /// Card the wilds of the hand count as, `None` without wilds.
+    /// A hand made only of wilds counts them as the strongest card.
+    pub fn wild_target(&self, cards: &[Card]) -> Option<Card> {
+        if !cards.iter().any(|card| self.is_wild(*card)) {
+            return None;
+        }
+        let mut counts: HashMap<Card, usize> = HashMap::new();
+        for card in cards.iter().filter(|card| !self.is_wild(**card)) {
+            *counts.entry(*card).or_default() += 1;
+        }
+        let target = match self.upgrade {
+            WildUpgrade::MostFrequent => counts
+                .into_iter()
+                .max_by_key(|(card, count)| (*count, self.rank(*card)))
+                .map(|(card, _)| card),
+            WildUpgrade::HighestCard => counts.into_keys().max_by_key(|card| self.rank(*card)),
+        };
+        target.or_else(|| ALL_CARDS.into_iter().max_by_key(|card| self.rank(*card)))
+    }
+
+    pub fn strength(&self, cards: &[Card]) -> Strength {
+        let target = self.wild_target(cards);
+        let mut counts: HashMap<Card, u8> = HashMap::new();
+        for card in cards {
+            let card = match target {
+                Some(target) if self.is_wild(*card) => target,
+                _ => *card,
+            };
+            *counts.entry(card).or_default() += 1;
+        }
+        Strength::new(counts.into_values().collect())
+    }
+}
+
+fn parse_cards(cards: &str) -> Result<Vec<Card>, Error> {
+    cards
+        .chars()
+        .map(|ch| {
+            char_to_card(ch)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Invalid card '{ch}'")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cards, Ruleset, WildUpgrade};
+    use crate::Card;
+
+    fn strength(ruleset: &Ruleset, hand: &str) -> String {
+        ruleset.strength(&parse_cards(hand).unwrap()).to_string()
+    }
+
+    #[test]
+    fn shoud_rank_jacks_and_jokers() {
+        let standard = Ruleset::standard();
+        assert!(standard.rank(Card::Jack) > standard.rank(Card::Ten));
+        assert!(standard.rank(Card::Jack) < standard.rank(Card::Queen));
+        assert_eq!(strength(&standard, "KTJJT"), "two pair");
+
+        let joker = Ruleset::joker();
+        assert_eq!(joker.rank(Card::Jack), 0);
+        assert_eq!(strength(&joker, "KTJJT"), "four of a kind");
+        assert_eq!(strength(&joker, "JJJJJ"), "five of a kind");
+        assert_eq!(
+            joker.wild_target(&parse_cards("QQQJA").unwrap()),
+            Some(Card::Queen)
+        );
+        assert_eq!(joker.wild_target(&parse_cards("32T3K").unwrap()), None);
+    }
+
+    #[test]
+    fn shoud_build_custom_rulesets() {
+        let args: Vec<String> = ["custom", "J23456789TQKA", "J2", "highest-card"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let custom = Ruleset::from_args(&args).unwrap();
+        assert_eq!(custom.upgrade, WildUpgrade::HighestCard);
+        // Both wilds copy the King instead of joining the pair of Tens
+        assert_eq!(strength(&custom, "KTJ2T"), "full house");
+        assert_eq!(strength(&Ruleset::joker(), "KTJ2T"), "three of a kind");
+
+        let invalid = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            Ruleset::from_args(&args).unwrap_err().to_string()
+        };
+        assert_eq!(invalid(&["custom", "23456789TJQK"]), "Card Ace has no rank");
+        assert_eq!(
+            invalid(&["custom", "23456789TJQKAA"]),
+            "Card Ace is ranked twice"
+        );
+        assert_eq!(invalid(&["custom", "23456789TJQKX"]), "Invalid card 'X'");
+        assert!(Ruleset::from_args(&["poker".to_owned()]).is_err());
+        assert!(Ruleset::standard().with_hand_size(0).is_err());
+        assert!(Ruleset::standard().with_hand_size(256).is_err());
+        assert!(Ruleset::standard().with_hand_size(255).is_ok());
+    }
+}
@@ -1,13 +1,7 @@
-use std::{collections::HashMap, io::Error};
+mod network;
 
-use regex::Regex;
-
-#[derive(Debug)]
-struct Node {
-    val: String,
-    left: String,
-    right: String,
-}
+use network::{parse_directions, Direction, Network};
+use std::io::{Error, ErrorKind};
 
 fn least_common_multiple(nums: &[usize]) -> usize {
     let mut result = 1;
@@ -25,97 +19,47 @@ fn gcd(a: usize, b: usize) -> usize {
     }
 }
 
-fn parse(lines: Vec<String>) -> Result<(Vec<char>, Vec<Node>), Error> {
-    let re = Regex::new(r"([A-Z0-9]{3}) = \(([A-Z0-9]{3}), ([A-Z0-9]{3})\)").unwrap();
-
+fn parse(lines: Vec<String>) -> Result<(Vec<Direction>, Network), Error> {
     // here the directions
-    let directions: Vec<char> = lines.get(0).unwrap().chars().collect();
+    let first = lines
+        .first()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Empty network"))?;
+    let directions = parse_directions(first)?;
 
     // and nodes
-    let nodes: Vec<Node> = re
-        .captures_iter(&lines.concat())
-        .map(|caps| Node {
-            val: caps[1].to_string(),
-            left: caps[2].to_string(),
-            right: caps[3].to_string(),
-        })
-        .collect();
-    Ok((directions, nodes))
+    let network = Network::parse(lines.get(1..).unwrap_or_default(), 2)?;
+    Ok((directions, network))
 }
 
-fn part1(input: String) -> Result<i32, Error> {
+fn part1(input: String) -> Result<usize, Error> {
     let lines = utils::read_input_file_as_vec(input)?;
 
-    let (directions, nodes) = parse(lines)?;
-
-    let mut directions_iter = directions.iter().cycle();
-
-    let mut curr_node = "AAA".to_string();
-    let mut output = 0;
-
-    while curr_node != "ZZZ" {
-        let direction = *directions_iter.next().unwrap();
-        if direction == 'R' {
-            curr_node = nodes
-                .iter()
-                .find(|n| n.val == curr_node)
-                .unwrap()
-                .right
-                .clone();
-        } else {
-            curr_node = nodes
-                .iter()
-                .find(|n| n.val == curr_node)
-                .unwrap()
-                .left
-                .clone();
-        }
-        output += 1;
-    }
-    println!("Output: {}", output);
+    let (directions, network) = parse(lines)?;
 
-    Ok(output)
+    let start = network.index_of("AAA")?;
+    let end = network.index_of("ZZZ")?;
+    network.steps(start, &directions, |node| node == end)
 }
 
 fn part2(input: String) -> Result<usize, Error> {
     let lines = utils::read_input_file_as_vec(input)?;
 
-    let (directions, nodes) = parse(lines)?;
+    let (directions, network) = parse(lines)?;
 
-    let starting_positions: Vec<String> = nodes
+    let is_end: Vec<bool> = network
+        .names()
         .iter()
-        .filter(|node| node.val.ends_with('A'))
-        .map(|node| node.val.clone())
+        .map(|name| name.ends_with('Z'))
         .collect();
 
-    let mut end_counts = HashMap::<String, usize>::new();
-
-    for start in starting_positions.iter() {
-        let mut current_node_name = start.clone();
-        let mut count = 0;
-        let mut directions_iter = directions.iter().cycle();
-
-        while !current_node_name.ends_with('Z') {
-            let current_instruction = directions_iter.next().unwrap();
-            let current_node = nodes.iter().find(|n| n.val == current_node_name).unwrap();
-
-            let next_node_name = if *current_instruction == 'R' {
-                current_node.right.clone()
-            } else {
-                current_node.left.clone()
-            };
-
-            current_node_name = next_node_name;
-            count += 1;
+    let mut counts = Vec::new();
+    for (start, name) in network.names().iter().enumerate() {
+        if name.ends_with('A') {
+            counts.push(network.steps(start, &directions, |node| is_end[node])?);
         }
-
-        end_counts.insert(start.clone(), count);
     }
 
-    let counts = end_counts.values().cloned().collect::<Vec<_>>();
-    let lcm = least_common_multiple(&counts);
-
-    Ok(lcm)
+    Ok(least_common_multiple(&counts))
 }
 
 fn main() -> Result<(), Error> {
@@ -159,4 +103,15 @@ mod tests {
         assert_eq!(result, 6);
         Ok(())
     }
+
+    #[test]
+    fn should_solve_real_input() -> Result<(), String> {
+        let result = part1("src/resources/input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 17873);
+        let result = part2("src/resources/input.txt".to_owned())
+            .map_err(|e| format!("Test failed with error: {:?}", e))?;
+        assert_eq!(result, 15746133679061);
+        Ok(())
+    }
 }
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// Nodes interned by name, each one pointing to the index of its left and right nodes.
+#[derive(Debug)]
+pub struct Network {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Split a `NAME = (LEFT, RIGHT)` line, names being anything but blanks and separators.
+fn parse_node(line: &str) -> Option<(&str, &str, &str)> {
+    let (name, links) = line.split_once('=')?;
+    let links = links.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let (left, right) = links.split_once(',')?;
+    let names = (name.trim(), left.trim(), right.trim());
+    [names.0, names.1, names.2]
+        .iter()
+        .all(|name| {
+            !name.is_empty()
+                && !name
+                    .chars()
+                    .any(|c| c.is_whitespace() || "=(),".contains(c))
+        })
+        .then_some(names)
+}
+
+pub fn parse_directions(line: &str) -> Result<Vec<Direction>, Error> {
+    let directions: Vec<Direction> = line
+        .trim()
+        .chars()
+        .map(|c| match c {
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            _ => Err(invalid(format!("Invalid direction '{c}'"))),
+        })
+        .collect::<Result<_, _>>()?;
+    if directions.is_empty() {
+        return Err(invalid("No directions".to_owned()));
+    }
+    Ok(directions)
+}
+
+impl Network {
+    /// Parse the node lines, every referenced node must be defined exactly once.
+    /// `first_line` is the line number of the first node, for the error messages.
+    pub fn parse(lines: &[String], first_line: usize) -> Result<Self, Error> {
+        let mut nodes = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let node = parse_node(line).ok_or_else(|| {
+                invalid(format!(
+                    "Line {}: expected 'NAME = (LEFT, RIGHT)', got '{line}'",
+                    first_line + idx
+                ))
+            })?;
+            nodes.push(node);
+        }
+
+        let mut names = Vec::with_capacity(nodes.len());
+        let mut index = HashMap::with_capacity(nodes.len());
+        for (name, _, _) in &nodes {
+            if index.insert(name.to_string(), names.len()).is_some() {
+                return Err(invalid(format!("Node '{name}' is defined twice")));
+            }
+            names.push(name.to_string());
+        }
+
+        let resolve = |from: &str, to: &str| {
+            index
+                .get(to)
+                .copied()
+                .ok_or_else(|| invalid(format!("Node '{from}' refers to unknown node '{to}'")))
+        };
+        let mut left = Vec::with_capacity(nodes.len());
+        let mut right = Vec::with_capacity(nodes.len());
+        for (name, to_left, to_right) in &nodes {
+            left.push(resolve(name, to_left)?);
+            right.push(resolve(name, to_right)?);
+        }
+
+        Ok(Network {
+            names,
+            index,
+            left,
+            right,
+        })
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn index_of(&self, name: &str) -> Result<usize, Error> {
+        self.index
+            .get(name)
+            .copied()
+            .ok_or_else(|| invalid(format!("Unknown node '{name}'")))
+    }
+
+    pub fn next(&self, node: usize, direction: Direction) -> usize {
+        match direction {
+            Direction::Left => self.left[node],
+            Direction::Right => self.right[node],
+        }
+    }
+
+    /// Steps needed to reach a node accepted by `is_end`, following the directions in loop.
+    ///
+    /// Once every (node, position in the directions) pair has been seen, the walk is cycling
+    /// without ever reaching an end.
+    pub fn steps(
+        &self,
+        start: usize,
+        directions: &[Direction],
+        is_end: impl Fn(usize) -> bool,
+    ) -> Result<usize, Error> {
+        let limit = self.names.len() * directions.len();
+        let mut node = start;
+        for (steps, direction) in directions.iter().cycle().enumerate() {
+            if is_end(node) {
+                return Ok(steps);
+            }
+            if steps > limit {
+                break;
+            }
+            node = self.next(node, *direction);
+        }
+        Err(invalid(format!(
+            "No end node is reachable from '{}'",
+            self.names[start]
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_directions, Direction, Network};
+
+    fn lines(nodes: &[&str]) -> Vec<String> {
+        nodes.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn should_intern_arbitrary_names() {
+        let network = Network::parse(
+            &lines(&[
+                "start = (middle-node, start)",
+                "middle-node = (start, é)",
+                "é = (é, é)",
+            ]),
+            3,
+        )
+        .unwrap();
+        let start = network.index_of("start").unwrap();
+        let end = network.index_of("é").unwrap();
+        let directions = parse_directions("LR").unwrap();
+        assert_eq!(network.next(start, Direction::Left), 1);
+        assert_eq!(
+            network
+                .steps(start, &directions, |node| node == end)
+                .unwrap(),
+            2
+        );
+        assert_eq!(network.names().len(), 3);
+    }
+
+    #[test]
+    fn should_reject_invalid_networks() {
+        let error = |nodes: &[&str]| Network::parse(&lines(nodes), 3).unwrap_err().to_string();
+
+        assert_eq!(
+            error(&["AAA = (BBB, AAA)"]),
+            "Node 'AAA' refers to unknown node 'BBB'"
+        );
+        assert_eq!(
+            error(&["AAA = (AAA, AAA)", "AAA = (AAA, AAA)"]),
+            "Node 'AAA' is defined twice"
+        );
+        assert_eq!(
+            error(&["AAA = (AAA, AAA)", "", "AAA (AAA, AAA)"]),
+            "Line 5: expected 'NAME = (LEFT, RIGHT)', got 'AAA (AAA, AAA)'"
+        );
+        assert!(parse_directions("LRX").is_err());
+        assert!(parse_directions("").is_err());
+
+        let network = Network::parse(&lines(&["AAA = (AAA, BBB)", "BBB = (AAA, BBB)"]), 3).unwrap();
+        let directions = parse_directions("L").unwrap();
+        let error = network.steps(0, &directions, |node| node == 1).unwrap_err();
+        assert_eq!(error.to_string(), "No end node is reachable from 'AAA'");
+    }
+}
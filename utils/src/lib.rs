@@ -11,3 +11,20 @@ pub fn read_input_file_as_vec(input: String) -> Result<Vec<String>, Error> {
     let lines_arr: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
     Ok(lines_arr)
 }
+
+/// Small SplitMix64 generator, good enough for reproducible simulations and generated inputs.
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}